@@ -0,0 +1,82 @@
+//! A reference implementation of the external hasher protocol used by `ore mine --external-hasher`.
+//! It hashes on a single thread and can be run with:
+//!
+//!     cargo build --release --example external_hasher
+//!     ore mine --external-hasher target/release/examples/external_hasher
+
+use std::{
+    io::{stdin, stdout, BufRead, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use solana_program::{
+    keccak::{hashv, Hash as KeccakHash},
+    pubkey::Pubkey,
+};
+
+fn main() {
+    let mut job: Option<(Arc<AtomicBool>, JoinHandle<()>)> = None;
+    for line in stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts.as_slice() {
+            ["work", challenge, authority, difficulty, nonce_start, nonce_end] => {
+                stop(job.take());
+                let (Ok(challenge), Ok(authority), Ok(difficulty), Ok(nonce_start), Ok(nonce_end)) = (
+                    KeccakHash::from_str(challenge),
+                    Pubkey::from_str(authority),
+                    KeccakHash::from_str(difficulty),
+                    nonce_start.parse::<u64>(),
+                    nonce_end.parse::<u64>(),
+                ) else {
+                    eprintln!("Invalid work: {}", line);
+                    println!("done");
+                    continue;
+                };
+                let stop = Arc::new(AtomicBool::new(false));
+                let handle = std::thread::spawn({
+                    let stop = stop.clone();
+                    move || {
+                        let mut stdout = stdout();
+                        let mut searched = 0;
+                        for nonce in nonce_start..nonce_end {
+                            if nonce % 10_000 == 0 && stop.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            searched += 1;
+                            let hash = hashv(&[
+                                challenge.as_ref(),
+                                authority.as_ref(),
+                                nonce.to_le_bytes().as_slice(),
+                            ]);
+                            if hash.le(&difficulty) {
+                                writeln!(stdout, "nonce {}", nonce).ok();
+                                stdout.flush().ok();
+                            }
+                        }
+                        writeln!(stdout, "done {}", searched).ok();
+                        stdout.flush().ok();
+                    }
+                });
+                job = Some((stop, handle));
+            }
+            ["stop"] => stop(job.take()),
+            _ => eprintln!("Unknown command: {}", line),
+        }
+    }
+    stop(job);
+}
+
+fn stop(job: Option<(Arc<AtomicBool>, JoinHandle<()>)>) {
+    if let Some((stop, handle)) = job {
+        stop.store(true, Ordering::Relaxed);
+        handle.join().ok();
+    }
+}
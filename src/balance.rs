@@ -13,7 +13,7 @@ impl Miner {
             if let Ok(address) = Pubkey::from_str(&address) {
                 address
             } else {
-                println!("Invalid address: {:?}", address);
+                log::error!("Invalid address: {:?}", address);
                 return;
             }
         } else {
@@ -34,7 +34,7 @@ impl Miner {
                 }
             }
             Err(err) => {
-                println!("{:?}", err);
+                log::error!("{:?}", err);
             }
         }
     }
@@ -1,20 +1,85 @@
-use ore::{state::Bus, utils::AccountDeserialize, BUS_ADDRESSES};
+use std::io::{stdout, IsTerminal, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    style::Color,
+    terminal::{Clear, ClearType},
+};
+use ore::{
+    state::{Bus, Treasury},
+    utils::AccountDeserialize,
+    BUS_ADDRESSES, BUS_COUNT, BUS_EPOCH_REWARDS, TREASURY_ADDRESS,
+};
+use serde::Serialize;
 use solana_client::{client_error::Result, nonblocking::rpc_client::RpcClient};
 use solana_sdk::commitment_config::CommitmentConfig;
 
-use crate::Miner;
+use crate::{
+    logger, table::Table, utils::amount_u64_to_f64, BusSort, BussesArgs, Miner, OutputFormat,
+};
+
+#[derive(Clone, Serialize)]
+struct BusRow {
+    id: usize,
+    address: String,
+    rewards: Option<f64>,
+    budget_remaining: Option<f64>,
+    change: Option<f64>,
+    drained: bool,
+    error: Option<String>,
+    #[serde(skip)]
+    raw_rewards: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct BussesView {
+    timestamp: i64,
+    busses: Vec<BusRow>,
+    total_rewards: f64,
+    total_budget_remaining: f64,
+}
 
 impl Miner {
-    pub async fn busses(&self) {
+    pub async fn busses(&self, args: BussesArgs) {
         let client =
             RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        for address in BUS_ADDRESSES.iter() {
-            let data = client.get_account_data(address).await.unwrap();
-            match Bus::try_from_bytes(&data) {
-                Ok(bus) => {
-                    println!("Bus {}: {:} ORE", bus.id, bus.rewards);
+        let mut last: Option<Vec<BusRow>> = None;
+        loop {
+            let (mut rows, reward_rate) = match fetch_busses(&client).await {
+                Ok(busses) => busses,
+                Err(err) => {
+                    log::error!("Error fetching busses: {:?}", err);
+                    match args.watch {
+                        Some(interval) => tokio::time::sleep(interval).await,
+                        None => return,
+                    }
+                    continue;
+                }
+            };
+
+            // Compare against the last refresh, so busses which can no longer pay out stand out
+            let threshold = reward_rate.max(1);
+            if let Some(last) = last.as_ref() {
+                for (row, last) in rows.iter_mut().zip(last.iter()) {
+                    if let Some((now, before)) = row.raw_rewards.zip(last.raw_rewards) {
+                        row.change = Some(amount_u64_to_f64(now) - amount_u64_to_f64(before));
+                        row.drained = now < threshold && before >= threshold;
+                    }
                 }
-                Err(_) => {}
+            }
+            last = Some(rows.clone());
+            match args.sort {
+                BusSort::Id => rows.sort_by_key(|row| row.id),
+                BusSort::Rewards => rows.sort_by_key(|row| std::cmp::Reverse(row.raw_rewards)),
+            }
+            if args.reverse {
+                rows.reverse();
+            }
+            print_busses(&args, rows);
+            match args.watch {
+                Some(interval) => tokio::time::sleep(interval).await,
+                None => return,
             }
         }
     }
@@ -26,3 +91,113 @@ impl Miner {
         Ok(*Bus::try_from_bytes(&data).unwrap())
     }
 }
+
+/// Fetches every bus and the reward rate in one request. A bus which is missing or fails to parse
+/// gets an error.
+async fn fetch_busses(client: &RpcClient) -> Result<(Vec<BusRow>, u64)> {
+    let mut addresses = vec![TREASURY_ADDRESS];
+    addresses.extend(BUS_ADDRESSES.iter());
+    let accounts = client.get_multiple_accounts(&addresses).await?;
+    let reward_rate = accounts[0]
+        .as_ref()
+        .and_then(|a| Treasury::try_from_bytes(&a.data).ok())
+        .map_or(0, |treasury| treasury.reward_rate);
+    let rows = accounts[1..]
+        .iter()
+        .zip(BUS_ADDRESSES.iter())
+        .enumerate()
+        .map(|(id, (account, address))| {
+            let rewards = match account.as_ref() {
+                Some(account) => Bus::try_from_bytes(&account.data)
+                    .map(|bus| bus.rewards)
+                    .map_err(|err| format!("Failed to parse bus: {:?}", err)),
+                None => Err("Account not found".to_string()),
+            };
+            BusRow {
+                id,
+                address: address.to_string(),
+                rewards: rewards.as_ref().ok().map(|r| amount_u64_to_f64(*r)),
+                budget_remaining: rewards
+                    .as_ref()
+                    .ok()
+                    .map(|r| *r as f64 / BUS_EPOCH_REWARDS as f64),
+                change: None,
+                drained: false,
+                error: rewards.as_ref().err().cloned(),
+                raw_rewards: rewards.ok(),
+            }
+        })
+        .collect();
+    Ok((rows, reward_rate))
+}
+
+fn print_busses(args: &BussesArgs, rows: Vec<BusRow>) {
+    let total: u64 = rows.iter().filter_map(|row| row.raw_rewards).sum();
+    let view = BussesView {
+        timestamp: chrono::Utc::now().timestamp(),
+        total_rewards: amount_u64_to_f64(total),
+        total_budget_remaining: total as f64 / (BUS_EPOCH_REWARDS * BUS_COUNT as u64) as f64,
+        busses: rows,
+    };
+    match args.format {
+        OutputFormat::Json if args.watch.is_some() => {
+            println!("{}", serde_json::to_string(&view).unwrap());
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&view).unwrap());
+        }
+        OutputFormat::Table | OutputFormat::Csv => {
+            let mut headers = vec!["Bus", "Address", "Rewards (ORE)", "Budget left"];
+            if args.watch.is_some() {
+                headers.push("Change (ORE)");
+            }
+            let mut table = Table::new(&headers);
+            for row in view.busses.iter() {
+                let mut cells = vec![row.id.to_string(), row.address.clone()];
+                match (row.rewards, row.budget_remaining, row.error.as_ref()) {
+                    (Some(rewards), Some(remaining), _) => {
+                        cells.push(format!("{:.9}", rewards));
+                        cells.push(format!("{:.1}%", remaining * 100.0));
+                    }
+                    (_, _, error) => {
+                        cells.push(format!("Error: {}", error.map_or("", |e| e.as_str())));
+                        cells.push("-".to_string());
+                    }
+                }
+                if args.watch.is_some() {
+                    cells.push(match row.change {
+                        Some(change) if change != 0.0 => format!("{:+.9}", change),
+                        _ => String::new(),
+                    });
+                }
+                match row.drained {
+                    true => table.add_highlighted_row(cells, Color::Red),
+                    false => table.add_row(cells),
+                }
+            }
+            let mut totals = vec![
+                "Total".to_string(),
+                String::new(),
+                format!("{:.9}", view.total_rewards),
+                format!("{:.1}%", view.total_budget_remaining * 100.0),
+            ];
+            if args.watch.is_some() {
+                totals.push(String::new());
+            }
+            table.add_row(totals);
+            if let OutputFormat::Csv = args.format {
+                table.print_csv();
+                return;
+            }
+            if args.watch.is_some() {
+                // Redraw in place on a terminal, otherwise append each refresh
+                if logger::tui() && stdout().is_terminal() {
+                    execute!(stdout(), MoveTo(0, 0), Clear(ClearType::All)).ok();
+                }
+                println!("{}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+            }
+            table.print();
+            stdout().flush().ok();
+        }
+    }
+}
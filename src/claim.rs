@@ -2,20 +2,34 @@ use std::str::FromStr;
 
 use ore::{self, state::Proof, utils::AccountDeserialize};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_program::pubkey::Pubkey;
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
 use solana_sdk::{
-    commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction,
-    signature::Signer,
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Signature, Signer},
 };
 
 use crate::{
     cu_limits::{CU_LIMIT_ATA, CU_LIMIT_CLAIM},
+    events::{self, Event},
     utils::proof_pubkey,
     Miner,
 };
 
+/// A claim that landed.
+pub struct Claimed {
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub signature: Signature,
+}
+
 impl Miner {
-    pub async fn claim(&self, cluster: String, beneficiary: Option<String>, amount: Option<f64>) {
+    pub async fn claim(
+        &self,
+        cluster: String,
+        beneficiary: Option<String>,
+        amount: Option<f64>,
+    ) -> Option<Claimed> {
         let signer = self.signer();
         let pubkey = signer.pubkey();
         let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
@@ -34,8 +48,8 @@ impl Miner {
                     proof.claimable_rewards
                 }
                 Err(err) => {
-                    println!("Error looking up claimable rewards: {:?}", err);
-                    return;
+                    log::error!("Error looking up claimable rewards: {:?}", err);
+                    return None;
                 }
             }
         };
@@ -43,22 +57,86 @@ impl Miner {
         let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_CLAIM);
         let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
         let ix = ore::instruction::claim(pubkey, beneficiary, amount);
-        println!("Submitting claim transaction...");
+        log::info!("Submitting claim transaction...");
         match self
             .send_and_confirm(&[cu_limit_ix, cu_price_ix, ix], false)
             .await
         {
             Ok(sig) => {
-                println!("Claimed {:} ORE to account {:}", amountf, beneficiary);
-                println!("{:?}", sig);
+                log::info!(
+                    "Claimed {:} ORE to account {:} in {}",
+                    amountf,
+                    beneficiary,
+                    sig
+                );
+                events::emit(Event::Claim {
+                    authority: pubkey.to_string(),
+                    beneficiary: beneficiary.to_string(),
+                    amount_ore: amountf,
+                    signature: sig.to_string(),
+                });
+                Some(Claimed {
+                    beneficiary,
+                    amount,
+                    signature: sig,
+                })
             }
             Err(err) => {
-                println!("Error: {:?}", err);
+                log::error!("Error: {:?}", err);
+                None
             }
         }
     }
 
-    async fn initialize_ata(&self) -> Pubkey {
+    /// Resolves the account claims to `address` should be sent to. An ORE token account is used
+    /// as is. Any other address is taken to be a wallet, whose ORE account is created if needed.
+    pub async fn resolve_beneficiary(&self, address: &str) -> Result<Pubkey, String> {
+        let address =
+            Pubkey::from_str(address).map_err(|_| format!("Invalid claim address: {}", address))?;
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        let account = client
+            .get_account_with_commitment(&address, CommitmentConfig::confirmed())
+            .await
+            .map_err(|err| format!("Failed to fetch claim address {}: {}", address, err))?
+            .value;
+        if let Some(account) = account.filter(|a| a.owner.eq(&spl_token::id())) {
+            return match spl_token::state::Account::unpack(&account.data) {
+                Ok(token_account) if token_account.mint.eq(&ore::MINT_ADDRESS) => Ok(address),
+                _ => Err(format!("{} is not an ORE token account", address)),
+            };
+        }
+
+        // Claim to the wallet's ORE account
+        let token_account = spl_associated_token_account::get_associated_token_address(
+            &address,
+            &ore::MINT_ADDRESS,
+        );
+        if let Ok(Some(_)) = client.get_token_account(&token_account).await {
+            return Ok(token_account);
+        }
+        let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_ATA);
+        let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
+        let ix =
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &self.signer().pubkey(),
+                &address,
+                &ore::MINT_ADDRESS,
+                &spl_token::id(),
+            );
+        log::info!("Creating ORE account {} for {}...", token_account, address);
+        self.send_and_confirm(&[cu_limit_ix, cu_price_ix, ix], false)
+            .await
+            .map_err(|err| {
+                format!(
+                    "Failed to create token account {}: {:?}",
+                    token_account, err
+                )
+            })?;
+        Ok(token_account)
+    }
+
+    pub async fn initialize_ata(&self) -> Pubkey {
         // Initialize client.
         let signer = self.signer();
         let client =
@@ -84,13 +162,13 @@ impl Miner {
             &ore::MINT_ADDRESS,
             &spl_token::id(),
         );
-        println!("Creating token account {}...", token_account_pubkey);
+        log::info!("Creating token account {}...", token_account_pubkey);
         match self
             .send_and_confirm(&[cu_limit_ix, cu_price_ix, ix], false)
             .await
         {
-            Ok(_sig) => println!("Created token account {:?}", token_account_pubkey),
-            Err(e) => println!("Transaction failed: {:?}", e),
+            Ok(_sig) => log::info!("Created token account {:?}", token_account_pubkey),
+            Err(e) => log::error!("Transaction failed: {:?}", e),
         }
 
         // Return token account address
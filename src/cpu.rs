@@ -0,0 +1,156 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use solana_sdk::{keccak::Hash as KeccakHash, pubkey::Pubkey};
+
+use crate::mine::{find_hash_par, HashCounters, HashParams};
+
+// How long a throttled thread hashes for before resting
+const DUTY_CYCLE: Duration = Duration::from_millis(100);
+
+// How long to measure each thread count for with --auto-threads
+const BENCHMARK_DURATION: Duration = Duration::from_secs(3);
+
+/// How hashing threads are scheduled.
+struct ThreadConfig {
+    cores: Vec<usize>,
+    nice: Option<i32>,
+    max_cpu: Option<u8>,
+}
+
+static CONFIG: OnceLock<ThreadConfig> = OnceLock::new();
+
+/// Sets the cores hashing threads are pinned to, in thread order, the nice level they run at and
+/// the share of time each may spend hashing.
+pub fn configure(cores: Vec<usize>, nice: Option<i32>, max_cpu: Option<u8>) {
+    CONFIG
+        .set(ThreadConfig {
+            cores,
+            nice,
+            max_cpu,
+        })
+        .ok();
+}
+
+/// Applies the configured core and nice level to the calling hashing thread.
+pub fn setup_hash_thread(thread: u64) {
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    if !config.cores.is_empty() {
+        let id = config.cores[thread as usize % config.cores.len()];
+        if !core_affinity::set_for_current(core_affinity::CoreId { id }) {
+            log::warn!("Failed to pin thread {} to core {}", thread, id);
+        }
+    }
+    if let Some(nice) = config.nice {
+        set_nice(nice);
+    }
+}
+
+/// Keeps a hashing thread busy for at most `--max-cpu` percent of the time.
+pub struct Throttle {
+    max_cpu: Option<u8>,
+    busy_since: Instant,
+}
+
+impl Throttle {
+    pub fn new() -> Self {
+        Self {
+            max_cpu: CONFIG
+                .get()
+                .and_then(|config| config.max_cpu)
+                .filter(|max_cpu| *max_cpu < 100),
+            busy_since: Instant::now(),
+        }
+    }
+
+    /// Sleeps in proportion to the time spent hashing since the last rest, once a duty cycle
+    /// has passed.
+    pub fn rest(&mut self) {
+        let Some(max_cpu) = self.max_cpu else {
+            return;
+        };
+        let busy = self.busy_since.elapsed();
+        if busy < DUTY_CYCLE {
+            return;
+        }
+        std::thread::sleep(busy * (100 - max_cpu as u32) / max_cpu as u32);
+        self.busy_since = Instant::now();
+    }
+}
+
+#[cfg(unix)]
+fn set_nice(nice: i32) {
+    // On Linux this only affects the calling thread
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        log::warn!(
+            "Failed to set nice level {}: {}",
+            nice,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn set_nice(_nice: i32) {
+    log::warn!("Nice levels are not supported on this platform");
+}
+
+/// Measures the hashrate of a range of thread counts up to `max_threads`, and returns the
+/// fastest.
+pub fn benchmark_threads(max_threads: u64) -> u64 {
+    let physical = num_cpus::get_physical() as u64;
+    let logical = num_cpus::get() as u64;
+    let mut candidates: BTreeSet<u64> = (0..)
+        .map(|i| 1u64 << i)
+        .take_while(|threads| *threads < max_threads)
+        .collect();
+    candidates.extend([physical, logical, max_threads]);
+    candidates.retain(|threads| (1..=max_threads).contains(threads));
+    log::info!(
+        "Detected {} physical and {} logical cores, benchmarking {:?} threads...",
+        physical,
+        logical,
+        candidates
+    );
+    let mut best = (1, 0.0);
+    for threads in candidates {
+        let hashrate = measure_hashrate(threads);
+        log::info!("{} threads: {:.0} H/s", threads, hashrate);
+        if hashrate > best.1 {
+            best = (threads, hashrate);
+        }
+    }
+    log::info!("Mining with {} threads", best.0);
+    best.0
+}
+
+/// Returns the hashrate of `threads` hashing threads, measured over a few seconds.
+pub fn measure_hashrate(threads: u64) -> f64 {
+    let params = HashParams {
+        challenge: KeccakHash::new_unique(),
+        authority: Pubkey::new_unique(),
+        difficulty: KeccakHash::new_from_array([0; 32]),
+        nonce_start: 0,
+        nonce_end: u64::MAX,
+    };
+    let counters = Arc::new(HashCounters::new(threads));
+    let stop = Arc::new(AtomicBool::new(false));
+    std::thread::spawn({
+        let stop = stop.clone();
+        move || {
+            std::thread::sleep(BENCHMARK_DURATION);
+            stop.store(true, Ordering::Relaxed);
+        }
+    });
+    let started_at = Instant::now();
+    find_hash_par(params, threads, &counters, stop, None);
+    counters.total() as f64 / started_at.elapsed().as_secs_f64()
+}
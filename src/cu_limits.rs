@@ -1,6 +1,6 @@
 pub const CU_LIMIT_REGISTER: u32 = 7660;
 pub const CU_LIMIT_CLAIM: u32 = 11_000;
-// pub const CU_LIMIT_TRANSFER: u32 = 30_000; // TODO
+pub const CU_LIMIT_TRANSFER: u32 = 30_000;
 pub const CU_LIMIT_ATA: u32 = 24_000;
 pub const CU_LIMIT_RESET: u32 = 12_200;
 pub const CU_LIMIT_MINE: u32 = 3200;
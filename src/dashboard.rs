@@ -0,0 +1,474 @@
+use std::{
+    collections::VecDeque,
+    io::{stderr, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ore::{BUS_EPOCH_REWARDS, EPOCH_DURATION};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, keccak::Hash as KeccakHash, pubkey::Pubkey};
+
+use crate::{
+    logger,
+    mine::{find_hash_par, HashCounters, HashParams},
+    progress::Progress,
+    utils::{
+        amount_u64_to_f64, fee_runway, format_duration, format_hashrate, get_miner_accounts,
+        lamports_to_sol, MinerAccounts,
+    },
+    Miner,
+};
+
+// How often to redraw the screen
+const DRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+// How often to refresh balances, busses and the treasury
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// The number of recent transactions to show
+const RECENT_TRANSACTIONS: usize = 5;
+
+// Whether the dashboard has the terminal in raw mode on the alternate screen
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Restores the terminal when dropped, so it is usable again however mining ends.
+pub struct TerminalGuard(());
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// A landed mine transaction, as shown in the dashboard.
+pub struct RecentTransaction {
+    pub landed_at: i64,
+    pub signature: String,
+    pub bus: u64,
+    pub attempts: u64,
+    pub landing_secs: f64,
+}
+
+/// What the mining loop reports to the dashboard.
+#[derive(Default)]
+pub struct DashboardState {
+    pub status: &'static str,
+    pub solutions: u64,
+    pub fees_spent: u64,
+    pub ore_earned: u64,
+    pub min_lamports: u64,
+    transactions: VecDeque<RecentTransaction>,
+    accounts: Option<MinerAccounts>,
+}
+
+impl DashboardState {
+    pub fn push_transaction(&mut self, transaction: RecentTransaction) {
+        self.transactions.push_front(transaction);
+        self.transactions.truncate(RECENT_TRANSACTIONS);
+    }
+}
+
+/// A full-screen view of the miner which also takes commands from the keyboard.
+pub struct Dashboard {
+    authority: Pubkey,
+    priority_fee: u64,
+    started_at: Instant,
+    counters: Arc<HashCounters>,
+    progress: Arc<Progress>,
+    stop: Arc<AtomicBool>,
+    state: Mutex<DashboardState>,
+    threads: AtomicU64,
+    paused: AtomicBool,
+    interrupt: AtomicBool,
+    claim_requested: AtomicBool,
+    closed: AtomicBool,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Dashboard {
+    /// Takes over the terminal and starts drawing. Log lines are shown in the dashboard until
+    /// it is closed. The terminal is restored when the returned guard is dropped, or on panic.
+    pub fn start(
+        cluster: String,
+        authority: Pubkey,
+        priority_fee: u64,
+        counters: Arc<HashCounters>,
+        progress: Arc<Progress>,
+        stop: Arc<AtomicBool>,
+        threads: u64,
+    ) -> Result<(Arc<Self>, TerminalGuard), String> {
+        terminal::enable_raw_mode().map_err(|err| format!("Failed to start dashboard: {}", err))?;
+        TERMINAL_ACTIVE.store(true, Ordering::Relaxed);
+        execute!(stderr(), EnterAlternateScreen, Hide).ok();
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            logger::capture(false);
+            default_hook(info);
+        }));
+        logger::capture(true);
+        let dashboard = Arc::new(Self {
+            authority,
+            priority_fee,
+            started_at: Instant::now(),
+            counters,
+            progress,
+            stop,
+            state: Mutex::new(DashboardState {
+                status: "Starting",
+                ..Default::default()
+            }),
+            threads: AtomicU64::new(threads),
+            paused: AtomicBool::new(false),
+            interrupt: AtomicBool::new(false),
+            claim_requested: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            handle: Mutex::new(None),
+        });
+        let handle = std::thread::spawn({
+            let dashboard = dashboard.clone();
+            move || dashboard.run()
+        });
+        *dashboard.handle.lock().unwrap() = Some(handle);
+        tokio::spawn(dashboard.clone().refresh(cluster));
+        Ok((dashboard, TerminalGuard(())))
+    }
+
+    /// Restores the terminal and writes captured log lines to it.
+    pub fn close(&self) {
+        if self.closed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().ok();
+        }
+        restore_terminal();
+        let lines = logger::captured(usize::MAX);
+        logger::capture(false);
+        for line in lines {
+            eprintln!("{}", line);
+        }
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut DashboardState)) {
+        f(&mut self.state.lock().unwrap());
+    }
+
+    pub fn threads(&self) -> u64 {
+        self.threads.load(Ordering::Relaxed)
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if a key was pressed which requires the current search to stop.
+    pub fn interrupted(&self) -> bool {
+        self.interrupt.load(Ordering::Relaxed)
+    }
+
+    pub fn take_interrupt(&self) -> bool {
+        self.interrupt.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn claim_requested(&self) -> bool {
+        self.claim_requested.load(Ordering::Relaxed)
+    }
+
+    pub fn take_claim_request(&self) -> bool {
+        self.claim_requested.swap(false, Ordering::Relaxed)
+    }
+
+    fn run(&self) {
+        while !self.closed.load(Ordering::Relaxed) {
+            if let Ok(true) = event::poll(DRAW_INTERVAL) {
+                if let Ok(Event::Key(key)) = event::read() {
+                    self.handle_key(key);
+                }
+            }
+            self.draw().ok();
+        }
+    }
+
+    fn handle_key(&self, key: KeyEvent) {
+        if key.kind.ne(&KeyEventKind::Press) {
+            return;
+        }
+        match key.code {
+            KeyCode::Char('c') | KeyCode::Char('C')
+                if key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.quit()
+            }
+            KeyCode::Char('p') => {
+                let paused = !self.paused.fetch_xor(true, Ordering::Relaxed);
+                log::info!("{}", if paused { "Paused" } else { "Resumed" });
+                self.interrupt.store(true, Ordering::Relaxed);
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                let threads = (self.threads() + 1).min(self.counters.threads());
+                self.set_threads(threads);
+            }
+            KeyCode::Char('-') => {
+                let threads = self.threads().saturating_sub(1).max(1);
+                self.set_threads(threads);
+            }
+            KeyCode::Char('c') => {
+                log::info!("Claiming rewards...");
+                self.claim_requested.store(true, Ordering::Relaxed);
+                self.interrupt.store(true, Ordering::Relaxed);
+            }
+            KeyCode::Char('q') => self.quit(),
+            _ => {}
+        }
+    }
+
+    fn set_threads(&self, threads: u64) {
+        if self.threads.swap(threads, Ordering::Relaxed).ne(&threads) {
+            log::info!("Mining with {} threads", threads);
+            self.interrupt.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops mining gracefully, or exits immediately if already stopping.
+    fn quit(&self) {
+        if self.stop.swap(true, Ordering::Relaxed) {
+            restore_terminal();
+            std::process::exit(130);
+        }
+        log::warn!("Shutting down, press q again to force exit...");
+    }
+
+    /// Periodically fetches the accounts shown in the dashboard.
+    async fn refresh(self: Arc<Self>, cluster: String) {
+        let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        while !self.closed.load(Ordering::Relaxed) {
+            interval.tick().await;
+            if let Ok(accounts) = get_miner_accounts(&client, self.authority).await {
+                self.state.lock().unwrap().accounts = Some(accounts);
+            }
+        }
+    }
+
+    fn draw(&self) -> std::io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        let state = self.state.lock().unwrap();
+        let threads = self.threads() as usize;
+        let rates = self.progress.rates();
+        let hashrate: f64 = rates.iter().take(threads).sum();
+        let bar_width = width.saturating_sub(40).clamp(10, 40);
+        let mut lines = vec![];
+
+        // Status
+        lines.push(format!(
+            "ORE miner {}    up {}",
+            self.authority,
+            format_duration(self.started_at.elapsed())
+        ));
+        let status = if self.paused() {
+            "Paused"
+        } else {
+            state.status
+        };
+        lines.push(format!(
+            "{} with {} threads    [p] pause  [+/-] threads  [c] claim  [q] quit",
+            status, threads
+        ));
+        lines.push(String::new());
+
+        // Hashing
+        lines.push(format!("Hashrate {}", format_hashrate(hashrate)));
+        let fastest = rates.iter().cloned().fold(1f64, f64::max);
+        for (i, rate) in rates.iter().take(threads).enumerate() {
+            lines.push(format!(
+                "  Thread {:<3} {} {}",
+                i,
+                bar(rate / fastest, bar_width),
+                format_hashrate(*rate)
+            ));
+        }
+        let challenge_hashes = self.progress.challenge_hashes() as f64;
+        let expected_hashes = self.progress.expected_hashes();
+        if expected_hashes > 0.0 {
+            lines.push(format!(
+                "Challenge  {} {:.0} of {:.0} expected hashes ({:.0}%)",
+                bar(challenge_hashes / expected_hashes, bar_width),
+                challenge_hashes,
+                expected_hashes,
+                challenge_hashes / expected_hashes * 100.0
+            ));
+        }
+        if let (Some(median), Some(likely)) = (self.progress.eta(0.5), self.progress.eta(0.9)) {
+            lines.push(format!(
+                "ETA        {} (50% chance), {} (90% chance)",
+                format_duration(median),
+                format_duration(likely)
+            ));
+        }
+        lines.push(String::new());
+
+        // Epoch and busses
+        if let Some(accounts) = state.accounts.as_ref() {
+            if let Some(treasury) = accounts.treasury {
+                let reset_in = treasury
+                    .last_reset_at
+                    .saturating_add(EPOCH_DURATION)
+                    .saturating_sub(chrono::Utc::now().timestamp());
+                let reset = if reset_in > 0 {
+                    format!("Epoch resets in {}s", reset_in)
+                } else {
+                    format!("Epoch reset overdue by {}s", -reset_in)
+                };
+                lines.push(format!(
+                    "{}    Reward rate {} ORE",
+                    reset,
+                    amount_u64_to_f64(treasury.reward_rate)
+                ));
+            }
+            for (i, rewards) in accounts.bus_rewards.iter().enumerate() {
+                lines.push(format!(
+                    "  Bus {}  {} {:.4} ORE",
+                    i,
+                    bar(*rewards as f64 / BUS_EPOCH_REWARDS as f64, bar_width),
+                    amount_u64_to_f64(*rewards)
+                ));
+            }
+            lines.push(format!(
+                "Wallet {:.4} ORE  {:.4} SOL (~{} mine txs)    Claimable {:.4} ORE",
+                amount_u64_to_f64(accounts.wallet_ore),
+                lamports_to_sol(accounts.wallet_sol),
+                fee_runway(
+                    accounts.wallet_sol.saturating_sub(state.min_lamports),
+                    self.priority_fee
+                ),
+                amount_u64_to_f64(accounts.proof.map_or(0, |p| p.claimable_rewards))
+            ));
+        } else {
+            lines.push("Fetching accounts...".to_string());
+        }
+        lines.push(format!(
+            "Session {} solutions    {:.4} ORE earned    {:.6} SOL fees",
+            state.solutions,
+            amount_u64_to_f64(state.ore_earned),
+            lamports_to_sol(state.fees_spent)
+        ));
+        lines.push(String::new());
+
+        // Recent transactions
+        lines.push("Recent transactions".to_string());
+        if state.transactions.is_empty() {
+            lines.push("  None yet".to_string());
+        }
+        for tx in state.transactions.iter() {
+            lines.push(format!(
+                "  {}  {}  bus {}  {} attempts  landed in {:.1}s",
+                chrono::DateTime::from_timestamp(tx.landed_at, 0)
+                    .map_or(String::new(), |t| t.format("%H:%M:%S").to_string()),
+                tx.signature,
+                tx.bus,
+                tx.attempts,
+                tx.landing_secs
+            ));
+        }
+        lines.push(String::new());
+
+        // Log lines fill the remaining space
+        lines.push("Log".to_string());
+        let log_lines = height.saturating_sub(lines.len()).saturating_sub(1);
+        for line in logger::captured(log_lines) {
+            lines.push(format!("  {}", line));
+        }
+
+        let mut out = stderr().lock();
+        queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+        for (row, line) in lines.iter().take(height).enumerate() {
+            let line: String = line.chars().take(width).collect();
+            queue!(out, MoveTo(0, row as u16), Print(line))?;
+        }
+        out.flush()
+    }
+}
+
+impl Miner {
+    /// Searches `params` for a hash while following dashboard commands. Pausing or changing
+    /// threads interrupts the search, which then continues from where it stopped. A claim request
+    /// ends the search, so the mining loop can claim. Returns the solution, if any, and the nonce
+    /// before which every nonce has been searched.
+    pub async fn find_hash_with_dashboard(
+        &self,
+        mut params: HashParams,
+        counters: &Arc<HashCounters>,
+        stop: &AtomicBool,
+        dashboard: &Dashboard,
+    ) -> (Option<(KeccakHash, u64)>, u64) {
+        loop {
+            if dashboard.claim_requested() {
+                return (None, params.nonce_start);
+            }
+            while dashboard.paused() && !stop.load(Ordering::Relaxed) {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                dashboard.take_interrupt();
+            }
+            if stop.load(Ordering::Relaxed) {
+                return (None, params.nonce_start);
+            }
+            dashboard.update(|state| state.status = "Mining");
+
+            // Forward stop requests and interrupts to the search
+            let search_stop = Arc::new(AtomicBool::new(false));
+            let finished = AtomicBool::new(false);
+            let solution = std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    while !finished.load(Ordering::Relaxed) {
+                        if stop.load(Ordering::Relaxed) || dashboard.interrupted() {
+                            search_stop.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                });
+                let solution = find_hash_par(
+                    params,
+                    dashboard.threads(),
+                    counters,
+                    search_stop.clone(),
+                    None,
+                );
+                finished.store(true, Ordering::Relaxed);
+                solution
+            });
+            let (solution, searched) = solution;
+            if solution.is_some() || !dashboard.take_interrupt() || searched >= params.nonce_end {
+                return (solution, searched);
+            }
+            params.nonce_start = searched;
+        }
+    }
+}
+
+fn restore_terminal() {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    execute!(stderr(), Show, LeaveAlternateScreen).ok();
+    terminal::disable_raw_mode().ok();
+}
+
+fn bar(ratio: f64, width: usize) -> String {
+    let filled = (ratio.clamp(0.0, 1.0) * width as f64).round() as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
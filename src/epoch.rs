@@ -0,0 +1,175 @@
+use ore::{
+    state::{Bus, Treasury},
+    utils::AccountDeserialize,
+    BUS_ADDRESSES, BUS_COUNT, BUS_EPOCH_REWARDS, EPOCH_DURATION, TREASURY_ADDRESS,
+};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{clock::Clock, sysvar};
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::{
+    table::Table,
+    utils::{amount_u64_to_f64, format_timestamp},
+    EpochArgs, Miner, OutputFormat,
+};
+
+#[derive(Serialize)]
+struct EpochView {
+    now: i64,
+    last_reset_at: i64,
+    next_reset_at: i64,
+    seconds_until_reset: i64,
+    reward_rate: f64,
+    budget: f64,
+    remaining: f64,
+    bus_rewards: Vec<f64>,
+    drained_busses: usize,
+    spend_rate_per_second: Option<f64>,
+    seconds_until_drained: Option<f64>,
+    drains_before_reset: Option<bool>,
+}
+
+impl Miner {
+    pub async fn epoch(&self, args: EpochArgs) {
+        // Fetch the clock, treasury and busses in one request
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        let mut addresses = vec![sysvar::clock::ID, TREASURY_ADDRESS];
+        addresses.extend(BUS_ADDRESSES.iter());
+        let accounts = match client.get_multiple_accounts(&addresses).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                log::error!("Error fetching accounts: {:?}", err);
+                return;
+            }
+        };
+        let Some(clock) = accounts[0]
+            .as_ref()
+            .and_then(|a| bincode::deserialize::<Clock>(&a.data).ok())
+        else {
+            log::error!("Failed to read the clock");
+            return;
+        };
+        let Some(treasury) = accounts[1]
+            .as_ref()
+            .and_then(|a| Treasury::try_from_bytes(&a.data).ok().copied())
+        else {
+            log::error!("Treasury account not found");
+            return;
+        };
+        let bus_rewards: Vec<u64> = accounts[2..]
+            .iter()
+            .map(|a| {
+                a.as_ref()
+                    .and_then(|a| Bus::try_from_bytes(&a.data).ok())
+                    .map_or(0, |bus| bus.rewards)
+            })
+            .collect();
+
+        // Project whether the busses drain before the reset, at the rate they have been spent so far
+        let budget = BUS_EPOCH_REWARDS.saturating_mul(BUS_COUNT as u64);
+        let remaining: u64 = bus_rewards.iter().sum();
+        let next_reset_at = treasury.last_reset_at.saturating_add(EPOCH_DURATION);
+        let elapsed = clock.unix_timestamp.saturating_sub(treasury.last_reset_at);
+        let seconds_until_reset = next_reset_at.saturating_sub(clock.unix_timestamp);
+        let spend_rate = (elapsed > 0)
+            .then(|| amount_u64_to_f64(budget.saturating_sub(remaining)) / elapsed as f64);
+        let seconds_until_drained = spend_rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| amount_u64_to_f64(remaining) / rate);
+        let view = EpochView {
+            now: clock.unix_timestamp,
+            last_reset_at: treasury.last_reset_at,
+            next_reset_at,
+            seconds_until_reset,
+            reward_rate: amount_u64_to_f64(treasury.reward_rate),
+            budget: amount_u64_to_f64(budget),
+            remaining: amount_u64_to_f64(remaining),
+            bus_rewards: bus_rewards.iter().map(|r| amount_u64_to_f64(*r)).collect(),
+            drained_busses: bus_rewards
+                .iter()
+                .filter(|r| **r < treasury.reward_rate)
+                .count(),
+            spend_rate_per_second: spend_rate,
+            seconds_until_drained,
+            drains_before_reset: (seconds_until_reset > 0).then(|| {
+                seconds_until_drained.map_or(false, |secs| secs < seconds_until_reset as f64)
+            }),
+        };
+
+        // Print
+        match args.format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&view).unwrap());
+            }
+            OutputFormat::Table | OutputFormat::Csv => {
+                let v = &view;
+                let mut table = Table::new(&["Field", "Value"]);
+                let mut add = |field: &str, value: String| {
+                    table.add_row(vec![field.to_string(), value]);
+                };
+                add("Cluster time", format_timestamp(v.now));
+                add(
+                    "Last reset",
+                    format!("{} ({}s ago)", format_timestamp(v.last_reset_at), elapsed),
+                );
+                add(
+                    "Next reset",
+                    match v.seconds_until_reset {
+                        secs if secs > 0 => {
+                            format!("{} (in {}s)", format_timestamp(v.next_reset_at), secs)
+                        }
+                        secs => format!(
+                            "{} (overdue by {}s)",
+                            format_timestamp(v.next_reset_at),
+                            -secs
+                        ),
+                    },
+                );
+                add("Reward rate (ORE)", format!("{:.9}", v.reward_rate));
+                add(
+                    "Epoch budget (ORE)",
+                    format!("{:.9} across {} busses", v.budget, BUS_COUNT),
+                );
+                add(
+                    "Remaining (ORE)",
+                    format!(
+                        "{:.9} ({:.1}%)",
+                        v.remaining,
+                        v.remaining / v.budget * 100.0
+                    ),
+                );
+                for (i, rewards) in v.bus_rewards.iter().enumerate() {
+                    add(&format!("Bus {}", i), format!("{:.9}", rewards));
+                }
+                add(
+                    "Drained busses",
+                    format!("{} of {}", v.drained_busses, BUS_COUNT),
+                );
+                add(
+                    "Spend rate (ORE/s)",
+                    v.spend_rate_per_second
+                        .map_or("-".to_string(), |rate| format!("{:.9}", rate)),
+                );
+                add(
+                    "Projection",
+                    match (v.drains_before_reset, v.seconds_until_drained) {
+                        (None, _) => "Reset is due, busses refill on the next reset".to_string(),
+                        (Some(true), Some(secs)) => format!(
+                            "Busses drain in ~{:.0}s, {:.0}s before the reset",
+                            secs,
+                            v.seconds_until_reset as f64 - secs
+                        ),
+                        (Some(_), _) => "Busses last until the reset".to_string(),
+                    },
+                );
+                if let OutputFormat::Csv = args.format {
+                    table.print_csv();
+                } else {
+                    table.print();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{stdout, Write},
+    sync::{Mutex, OnceLock},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+// With `--events ndjson`, the miner writes one JSON object per line to stdout, a file, or a unix
+// socket (`--events-to unix:<path>`). Every object has an `event` name and an RFC 3339
+// `timestamp`, plus the fields listed below. Fields are only ever added, never renamed or
+// removed. Hashes and pubkeys are base58, ORE amounts are decimal ORE, and fees are lamports.
+//
+//     session_start   authority, threads
+//     challenge       challenge, difficulty, reward_rate_ore, claimable_ore, balance_ore
+//     progress        hashes, hashrate
+//     solution_found  challenge, hash, nonce, hashes, solve_secs
+//     tx_sent         attempt, bus
+//     tx_landed       signature, bus, attempts, fees_lamports, landing_secs
+//     tx_failed       attempt, bus, error_class, error
+//     reset_sent      signature
+//     epoch_waiting   seconds_overdue
+//     claim           authority, beneficiary, amount_ore, signature
+//     low_balance     balance_lamports, min_lamports
+//
+// `progress` is emitted every few seconds while hashing, with `hashes` counted since the session
+// started and `hashrate` in hashes per second since the previous progress event. `tx_failed`
+// `error_class` is one of io, network, rpc, serialization, signing, transaction,
+// insufficient_funds, not_landed or other. `low_balance` is emitted when mining pauses because the
+// SOL balance fell below `--min-sol`.
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    SessionStart {
+        authority: String,
+        threads: u64,
+    },
+    Challenge {
+        challenge: String,
+        difficulty: String,
+        reward_rate_ore: f64,
+        claimable_ore: f64,
+        balance_ore: String,
+    },
+    Progress {
+        hashes: u64,
+        hashrate: f64,
+    },
+    SolutionFound {
+        challenge: String,
+        hash: String,
+        nonce: u64,
+        hashes: u64,
+        solve_secs: f64,
+    },
+    TxSent {
+        attempt: u64,
+        bus: u64,
+    },
+    TxLanded {
+        signature: String,
+        bus: u64,
+        attempts: u64,
+        fees_lamports: u64,
+        landing_secs: f64,
+    },
+    TxFailed {
+        attempt: u64,
+        bus: u64,
+        error_class: &'static str,
+        error: String,
+    },
+    ResetSent {
+        signature: String,
+    },
+    EpochWaiting {
+        seconds_overdue: i64,
+    },
+    Claim {
+        authority: String,
+        beneficiary: String,
+        amount_ore: f64,
+        signature: String,
+    },
+    LowBalance {
+        balance_lamports: u64,
+        min_lamports: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    #[serde(flatten)]
+    event: &'a Event,
+    timestamp: String,
+}
+
+enum Sink {
+    Stdout,
+    File(File),
+    #[cfg(unix)]
+    Socket(UnixStream),
+}
+
+static SINK: OnceLock<Mutex<Sink>> = OnceLock::new();
+
+/// Starts writing events to `target`, which is `-` for stdout, `unix:<path>` for a unix socket,
+/// or a file path to append to.
+pub fn init(target: &str) -> Result<(), String> {
+    let sink = if target.eq("-") {
+        Sink::Stdout
+    } else if let Some(path) = target.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            Sink::Socket(
+                UnixStream::connect(path)
+                    .map_err(|err| format!("Failed to connect to {}: {}", path, err))?,
+            )
+        }
+        #[cfg(not(unix))]
+        return Err(format!("Unix sockets are not supported: {}", path));
+    } else {
+        Sink::File(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(target)
+                .map_err(|err| format!("Failed to open {}: {}", target, err))?,
+        )
+    };
+    SINK.set(Mutex::new(sink)).ok();
+    Ok(())
+}
+
+/// Returns true if events are being written.
+pub fn enabled() -> bool {
+    SINK.get().is_some()
+}
+
+/// Writes an event, if events are enabled.
+pub fn emit(event: Event) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    let line = Line {
+        event: &event,
+        timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    };
+    let line = format!("{}\n", serde_json::to_string(&line).unwrap());
+    let result = match &mut *sink.lock().unwrap() {
+        Sink::Stdout => {
+            let mut stdout = stdout().lock();
+            stdout
+                .write_all(line.as_bytes())
+                .and_then(|_| stdout.flush())
+        }
+        Sink::File(file) => file.write_all(line.as_bytes()),
+        #[cfg(unix)]
+        Sink::Socket(socket) => socket.write_all(line.as_bytes()),
+    };
+    if let Err(err) = result {
+        log::warn!("Failed to write event: {}", err);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    io::{BufRead, BufReader, Lines, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use solana_program::keccak::{hashv, Hash as KeccakHash};
+
+use crate::mine::HashParams;
+
+// The external hasher protocol is line-oriented text over the hasher's stdin and stdout. Hashes and
+// pubkeys are base58 and nonces are decimal u64s.
+//
+// The miner sends one work item at a time:
+//
+//     work <challenge> <authority> <difficulty> <nonce_start> <nonce_end>
+//
+// The hasher searches `nonce_start..nonce_end` for nonces where
+// keccak(challenge || authority || nonce_le_bytes) <= difficulty, replying with a line for each
+// candidate:
+//
+//     nonce <nonce>
+//
+// Once the miner has verified a candidate it sends `stop`. The hasher ends every work item with a
+// single `done` line, either after exhausting the range or after being stopped:
+//
+//     done [<searched>]
+//
+// where the optional `searched` is the number of nonces from `nonce_start` on which it has
+// searched, so a stopped item can be resumed and its hashes counted. Any other line from the
+// hasher is ignored. See `examples/external_hasher.rs` for a reference implementation.
+
+// The number of nonces sent to the hasher in each work item
+pub const RANGE_SIZE: u64 = 1 << 28;
+
+pub struct ExternalHasher {
+    child: Child,
+    stdin: Mutex<ChildStdin>,
+    stdout: Mutex<Lines<BufReader<ChildStdout>>>,
+}
+
+impl ExternalHasher {
+    pub fn spawn(program: &str, args: &[String]) -> Result<Self, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Failed to start {}: {}", program, err))?;
+        let stdin = Mutex::new(child.stdin.take().unwrap());
+        let stdout = Mutex::new(BufReader::new(child.stdout.take().unwrap()).lines());
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Has the hasher search `nonce_start..nonce_end` until it returns a nonce which verifies, the
+    /// range is exhausted, or `stop` is set. Returns the solution, if one was found, and the nonce
+    /// before which every nonce has been searched.
+    pub fn find_hash(
+        &mut self,
+        params: HashParams,
+        stop: &AtomicBool,
+    ) -> Result<(Option<(KeccakHash, u64)>, u64), String> {
+        send(
+            &self.stdin,
+            &format!(
+                "work {} {} {} {} {}",
+                params.challenge,
+                params.authority,
+                params.difficulty,
+                params.nonce_start,
+                params.nonce_end
+            ),
+        )?;
+        let mut solution = None;
+        let finished = AtomicBool::new(false);
+        let searched = std::thread::scope(|scope| {
+            // Forward stop requests to the hasher while it works
+            scope.spawn(|| {
+                while !finished.load(Ordering::Relaxed) {
+                    if stop.load(Ordering::Relaxed) {
+                        send(&self.stdin, "stop").ok();
+                        return;
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+            });
+            let searched = self.read_until_done(&params, &mut solution);
+            finished.store(true, Ordering::Relaxed);
+            searched
+        })?;
+
+        // Without a count from the hasher, only an exhausted range is known to be searched
+        let searched_to = match (searched, solution) {
+            (Some(searched), _) => params.nonce_start.saturating_add(searched),
+            (None, Some((_, nonce))) => nonce.saturating_add(1),
+            (None, None) if !stop.load(Ordering::Relaxed) => params.nonce_end,
+            (None, None) => params.nonce_start,
+        };
+        Ok((solution, searched_to.min(params.nonce_end)))
+    }
+
+    /// Reads hasher output until the current work item is done, keeping the first valid nonce.
+    /// Returns the number of nonces the hasher reports having searched, if it does.
+    fn read_until_done(
+        &self,
+        params: &HashParams,
+        solution: &mut Option<(KeccakHash, u64)>,
+    ) -> Result<Option<u64>, String> {
+        loop {
+            let line = self.read()?;
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("nonce"), Some(nonce)) if solution.is_none() => {
+                    let Ok(nonce) = nonce.parse::<u64>() else {
+                        log::warn!("Invalid nonce from external hasher: {}", line);
+                        continue;
+                    };
+                    let hash = hashv(&[
+                        params.challenge.to_bytes().as_slice(),
+                        params.authority.to_bytes().as_slice(),
+                        nonce.to_le_bytes().as_slice(),
+                    ]);
+                    if hash.le(&params.difficulty) {
+                        *solution = Some((hash, nonce));
+                        send(&self.stdin, "stop")?;
+                    } else {
+                        log::warn!("External hasher returned an invalid nonce: {}", nonce);
+                    }
+                }
+                (Some("done"), searched) => return Ok(searched.and_then(|s| s.parse().ok())),
+                _ => {}
+            }
+        }
+    }
+
+    fn read(&self) -> Result<String, String> {
+        match self.stdout.lock().unwrap().next() {
+            Some(Ok(line)) => Ok(line),
+            Some(Err(err)) => Err(format!("Failed to read from external hasher: {}", err)),
+            None => Err("External hasher exited".to_string()),
+        }
+    }
+}
+
+fn send(stdin: &Mutex<ChildStdin>, line: &str) -> Result<(), String> {
+    let mut stdin = stdin.lock().unwrap();
+    writeln!(stdin, "{}", line)
+        .and_then(|_| stdin.flush())
+        .map_err(|err| format!("Failed to write to external hasher: {}", err))
+}
+
+impl Drop for ExternalHasher {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+
+/// A single miner in a fleet. Keys loaded from a keypair file can sign, while keys listed by
+/// address can only be inspected.
+pub struct FleetKey {
+    pub pubkey: Pubkey,
+    pub keypair_filepath: Option<PathBuf>,
+}
+
+impl FleetKey {
+    pub fn signer(&self) -> Option<Keypair> {
+        self.keypair_filepath
+            .as_ref()
+            .and_then(|filepath| read_keypair_file(filepath).ok())
+    }
+}
+
+/// Loads a fleet from either a directory of keypair files, a single keypair file, or a text file
+/// listing one keypair filepath or address per line. Blank lines and lines starting with `#` are
+/// ignored.
+pub fn load_fleet(path: &str) -> Result<Vec<FleetKey>, String> {
+    let path = Path::new(path);
+    let mut keys = vec![];
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+            .collect();
+        entries.sort();
+        for entry in entries {
+            keys.push(load_keypair_key(&entry)?);
+        }
+    } else if let Ok(keypair) = read_keypair_file(path) {
+        keys.push(FleetKey {
+            pubkey: keypair.pubkey(),
+            keypair_filepath: Some(path.to_path_buf()),
+        });
+    } else {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Ok(pubkey) = Pubkey::from_str(line) {
+                keys.push(FleetKey {
+                    pubkey,
+                    keypair_filepath: None,
+                });
+            } else {
+                keys.push(load_keypair_key(&base.join(line))?);
+            }
+        }
+    }
+
+    // Drop duplicates, keeping the first occurrence
+    let mut seen = std::collections::HashSet::new();
+    keys.retain(|key| seen.insert(key.pubkey));
+    if keys.is_empty() {
+        return Err(format!("No keys found in {}", path.display()));
+    }
+    Ok(keys)
+}
+
+fn load_keypair_key(filepath: &Path) -> Result<FleetKey, String> {
+    let keypair = read_keypair_file(filepath)
+        .map_err(|err| format!("Failed to read keypair {}: {}", filepath.display(), err))?;
+    Ok(FleetKey {
+        pubkey: keypair.pubkey(),
+        keypair_filepath: Some(filepath.to_path_buf()),
+    })
+}
@@ -0,0 +1,276 @@
+use std::str::FromStr;
+
+use ore::{state::Proof, utils::AccountDeserialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    packet::PACKET_DATA_SIZE,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+use crate::{
+    cu_limits::{CU_LIMIT_ATA, CU_LIMIT_CLAIM},
+    events::{self, Event},
+    fleet::load_fleet,
+    utils::{amount_u64_to_f64, get_multiple_accounts_batched, proof_pubkey},
+    FleetClaimArgs, Miner,
+};
+
+struct PendingClaim {
+    signer: Keypair,
+    amount: u64,
+}
+
+impl Miner {
+    pub async fn fleet_claim(&self, args: FleetClaimArgs) {
+        let fleet = match load_fleet(&args.keys) {
+            Ok(fleet) => fleet,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            }
+        };
+        let Ok(owner) = Pubkey::from_str(&args.to) else {
+            log::error!("Invalid address: {:?}", args.to);
+            return;
+        };
+        let beneficiary =
+            spl_associated_token_account::get_associated_token_address(&owner, &ore::MINT_ADDRESS);
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+
+        // Fetch claimable rewards of every proof
+        let proof_addresses: Vec<Pubkey> = fleet.iter().map(|k| proof_pubkey(k.pubkey)).collect();
+        let proofs = match get_multiple_accounts_batched(&client, &proof_addresses).await {
+            Ok(proofs) => proofs,
+            Err(err) => {
+                log::error!("Error fetching proofs: {:?}", err);
+                return;
+            }
+        };
+        let beneficiary_exists =
+            matches!(client.get_token_account(&beneficiary).await, Ok(Some(_)));
+
+        // Collect claims, skipping keys that cannot sign or have nothing to claim
+        let mut claims = vec![];
+        for (key, proof) in fleet.iter().zip(proofs.iter()) {
+            let amount = proof
+                .as_ref()
+                .and_then(|account| Proof::try_from_bytes(&account.data).ok())
+                .map_or(0, |proof| proof.claimable_rewards);
+            if amount == 0 {
+                log::info!("{}: nothing to claim", key.pubkey);
+                continue;
+            }
+            let Some(signer) = key.signer() else {
+                log::info!(
+                    "{}: skipping {} ORE, no keypair available",
+                    key.pubkey,
+                    amount_u64_to_f64(amount)
+                );
+                continue;
+            };
+            log::info!("{}: {} ORE", key.pubkey, amount_u64_to_f64(amount));
+            claims.push(PendingClaim { signer, amount });
+        }
+        if claims.is_empty() {
+            log::info!("No rewards to claim");
+            return;
+        }
+
+        // Pack claims into as few transactions as possible
+        let payer = self.keypair_filepath.as_ref().map(|_| self.signer());
+        let batches = pack_claims(
+            &claims,
+            payer.as_ref(),
+            beneficiary,
+            owner,
+            beneficiary_exists,
+            self.priority_fee,
+        );
+        let total: u64 = claims.iter().map(|c| c.amount).sum();
+        log::info!(
+            "\nClaiming {} ORE from {} proofs to {} in {} transactions",
+            amount_u64_to_f64(total),
+            claims.len(),
+            beneficiary,
+            batches.len()
+        );
+        if !beneficiary_exists {
+            log::info!("Token account {} will be created", beneficiary);
+        }
+        if args.dry_run {
+            return;
+        }
+
+        // Submit
+        let mut claimed = 0;
+        for (ixs, batch) in batches {
+            let mut signers: Vec<&Keypair> = vec![];
+            if let Some(payer) = payer.as_ref() {
+                signers.push(payer);
+            }
+            signers.extend(batch.iter().map(|c| &c.signer));
+            let amount: u64 = batch.iter().map(|c| c.amount).sum();
+            match self
+                .send_and_confirm_with_signers(&ixs, &signers, false)
+                .await
+            {
+                Ok(sig) => {
+                    claimed += amount;
+                    log::info!("Claimed {} ORE: {}", amount_u64_to_f64(amount), sig);
+                    for claim in batch.iter() {
+                        events::emit(Event::Claim {
+                            authority: claim.signer.pubkey().to_string(),
+                            beneficiary: beneficiary.to_string(),
+                            amount_ore: amount_u64_to_f64(claim.amount),
+                            signature: sig.to_string(),
+                        });
+                    }
+                }
+                Err(err) => {
+                    log::error!("Error: {:?}", err);
+                }
+            }
+        }
+        log::info!(
+            "Claimed {} of {} ORE to account {}",
+            amount_u64_to_f64(claimed),
+            amount_u64_to_f64(total),
+            beneficiary
+        );
+    }
+}
+
+/// Greedily packs claim instructions into transactions that fit within a single packet. If no
+/// payer is given, the first claimant of each transaction pays its fees.
+fn pack_claims<'a>(
+    claims: &'a [PendingClaim],
+    payer: Option<&Keypair>,
+    beneficiary: Pubkey,
+    owner: Pubkey,
+    beneficiary_exists: bool,
+    priority_fee: u64,
+) -> Vec<(Vec<Instruction>, &'a [PendingClaim])> {
+    let mut batches = vec![];
+    let mut start = 0;
+    while start < claims.len() {
+        let fee_payer = payer.map_or(claims[start].signer.pubkey(), |p| p.pubkey());
+        let create_ata = batches.is_empty() && !beneficiary_exists;
+        let mut end = start + 1;
+        let mut ixs = build_claim_ixs(
+            &claims[start..end],
+            fee_payer,
+            beneficiary,
+            owner,
+            create_ata,
+            priority_fee,
+        );
+        while end < claims.len() {
+            let next = build_claim_ixs(
+                &claims[start..end + 1],
+                fee_payer,
+                beneficiary,
+                owner,
+                create_ata,
+                priority_fee,
+            );
+            let tx = Transaction::new_with_payer(&next, Some(&fee_payer));
+            if bincode::serialized_size(&tx).unwrap_or(u64::MAX) > PACKET_DATA_SIZE as u64 {
+                break;
+            }
+            ixs = next;
+            end += 1;
+        }
+        batches.push((ixs, &claims[start..end]));
+        start = end;
+    }
+    batches
+}
+
+fn build_claim_ixs(
+    claims: &[PendingClaim],
+    fee_payer: Pubkey,
+    beneficiary: Pubkey,
+    owner: Pubkey,
+    create_ata: bool,
+    priority_fee: u64,
+) -> Vec<Instruction> {
+    let mut cu_limit = CU_LIMIT_CLAIM.saturating_mul(claims.len() as u32);
+    if create_ata {
+        cu_limit = cu_limit.saturating_add(CU_LIMIT_ATA);
+    }
+    let mut ixs = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+    ];
+    if create_ata {
+        ixs.push(
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &fee_payer,
+                &owner,
+                &ore::MINT_ADDRESS,
+                &spl_token::id(),
+            ),
+        );
+    }
+    for claim in claims {
+        ixs.push(ore::instruction::claim(
+            claim.signer.pubkey(),
+            beneficiary,
+            claim.amount,
+        ));
+    }
+    ixs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(n: usize) -> Vec<PendingClaim> {
+        (0..n)
+            .map(|i| PendingClaim {
+                signer: Keypair::new(),
+                amount: i as u64 + 1,
+            })
+            .collect()
+    }
+
+    fn assert_packed(claims: &[PendingClaim], payer: Option<&Keypair>, beneficiary_exists: bool) {
+        let beneficiary = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let batches = pack_claims(claims, payer, beneficiary, owner, beneficiary_exists, 1000);
+        assert!(batches.len() > 1);
+        assert_eq!(
+            batches.iter().map(|(_, batch)| batch.len()).sum::<usize>(),
+            claims.len()
+        );
+        for (ixs, batch) in batches.iter() {
+            let fee_payer = payer.map_or(batch[0].signer.pubkey(), |p| p.pubkey());
+            let mut signers: Vec<&Keypair> = payer.into_iter().collect();
+            signers.extend(batch.iter().map(|c| &c.signer));
+            let tx = Transaction::new_signed_with_payer(
+                ixs,
+                Some(&fee_payer),
+                &signers,
+                Default::default(),
+            );
+            assert!(bincode::serialized_size(&tx).unwrap() <= PACKET_DATA_SIZE as u64);
+        }
+    }
+
+    #[test]
+    fn pack_claims_fits_packet_without_payer() {
+        assert_packed(&claims(40), None, false);
+    }
+
+    #[test]
+    fn pack_claims_fits_packet_with_payer() {
+        let payer = Keypair::new();
+        assert_packed(&claims(40), Some(&payer), true);
+    }
+}
@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{
+    instruction::Instruction, native_token::sol_to_lamports, pubkey::Pubkey, system_instruction,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    packet::PACKET_DATA_SIZE,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+
+use crate::{
+    fleet::{load_fleet, FleetKey},
+    utils::{estimate_fee, get_multiple_accounts_batched, lamports_to_sol},
+    FleetFundArgs, Miner,
+};
+
+struct Transfer {
+    address: Pubkey,
+    lamports: u64,
+}
+
+// Compute units consumed by a single system transfer
+const CU_PER_TRANSFER: u32 = 150;
+
+// Compute units consumed by the compute unit limit and price instructions themselves
+const CU_COMPUTE_BUDGET: u32 = 2 * 150;
+
+impl Miner {
+    pub async fn fleet_fund(&self, args: FleetFundArgs) {
+        let fleet = match load_fleet(&args.keys) {
+            Ok(fleet) => fleet,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            }
+        };
+        let payer = match args.from.as_ref() {
+            Some(filepath) => match read_keypair_file(filepath) {
+                Ok(payer) => payer,
+                Err(err) => {
+                    log::error!("Failed to read keypair {}: {}", filepath, err);
+                    return;
+                }
+            },
+            None => self.signer(),
+        };
+        let target = sol_to_lamports(args.target);
+        let floor = args.floor.map_or(target, sol_to_lamports).min(target);
+        loop {
+            self.fund_once(&fleet, &payer, target, floor, args.dry_run)
+                .await;
+            if !args.daemon {
+                return;
+            }
+            tokio::time::sleep(Duration::from_secs(args.interval)).await;
+        }
+    }
+
+    async fn fund_once(
+        &self,
+        fleet: &[FleetKey],
+        payer: &Keypair,
+        target: u64,
+        floor: u64,
+        dry_run: bool,
+    ) {
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+
+        // Compute shortfalls of keys below the floor
+        let addresses: Vec<Pubkey> = fleet
+            .iter()
+            .map(|k| k.pubkey)
+            .filter(|pubkey| pubkey.ne(&payer.pubkey()))
+            .collect();
+        let accounts = match get_multiple_accounts_batched(&client, &addresses).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                log::error!("Error fetching balances: {:?}", err);
+                return;
+            }
+        };
+        let rent = match client.get_minimum_balance_for_rent_exemption(0).await {
+            Ok(rent) => rent,
+            Err(err) => {
+                log::error!("Error fetching rent: {:?}", err);
+                return;
+            }
+        };
+        let mut transfers = vec![];
+        for (address, account) in addresses.iter().zip(accounts.iter()) {
+            let balance = account.as_ref().map_or(0, |a| a.lamports);
+            if balance < floor {
+                // A transfer which creates an account must leave it rent exempt
+                let mut shortfall = target.saturating_sub(balance);
+                if account.is_none() {
+                    shortfall = shortfall.max(rent);
+                }
+                log::info!(
+                    "{}: {} SOL, sending {} SOL",
+                    address,
+                    lamports_to_sol(balance),
+                    lamports_to_sol(shortfall)
+                );
+                transfers.push(Transfer {
+                    address: *address,
+                    lamports: shortfall,
+                });
+            }
+        }
+        if transfers.is_empty() {
+            log::info!("All keys are funded");
+            return;
+        }
+
+        // Check the payer can cover the transfers and their fees
+        let batches = pack_transfers(&transfers, payer.pubkey(), self.priority_fee);
+        let total: u64 = transfers.iter().map(|t| t.lamports).sum();
+        let fees: u64 = batches
+            .iter()
+            .map(|(_, batch)| estimate_fee(transfer_cu_limit(batch), self.priority_fee))
+            .sum();
+        let payer_balance = client.get_balance(&payer.pubkey()).await.unwrap_or(0);
+        log::info!(
+            "Sending {} SOL to {} keys from {} ({} SOL) in {} transactions, ~{} SOL in fees",
+            lamports_to_sol(total),
+            transfers.len(),
+            payer.pubkey(),
+            lamports_to_sol(payer_balance),
+            batches.len(),
+            lamports_to_sol(fees)
+        );
+        if payer_balance < total.saturating_add(fees) {
+            log::error!("Insufficient SOL balance in {}", payer.pubkey());
+            return;
+        }
+        if dry_run {
+            return;
+        }
+
+        // Submit transfers in batches
+        for (ixs, batch) in batches {
+            match self
+                .send_and_confirm_with_signers(&ixs, &[payer], false)
+                .await
+            {
+                Ok(sig) => log::info!("Funded {} keys: {}", batch.len(), sig),
+                Err(err) => log::error!("Error: {:?}", err),
+            }
+        }
+    }
+}
+
+/// Greedily packs transfers into transactions that fit within a single packet.
+fn pack_transfers(
+    transfers: &[Transfer],
+    payer: Pubkey,
+    priority_fee: u64,
+) -> Vec<(Vec<Instruction>, &[Transfer])> {
+    let mut batches = vec![];
+    let mut start = 0;
+    while start < transfers.len() {
+        let mut end = start + 1;
+        let mut ixs = build_transfer_ixs(&transfers[start..end], payer, priority_fee);
+        while end < transfers.len() {
+            let next = build_transfer_ixs(&transfers[start..end + 1], payer, priority_fee);
+            let tx = Transaction::new_with_payer(&next, Some(&payer));
+            if bincode::serialized_size(&tx).unwrap_or(u64::MAX) > PACKET_DATA_SIZE as u64 {
+                break;
+            }
+            ixs = next;
+            end += 1;
+        }
+        batches.push((ixs, &transfers[start..end]));
+        start = end;
+    }
+    batches
+}
+
+fn build_transfer_ixs(
+    transfers: &[Transfer],
+    payer: Pubkey,
+    priority_fee: u64,
+) -> Vec<Instruction> {
+    let mut ixs = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(transfer_cu_limit(transfers)),
+        ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+    ];
+    for transfer in transfers {
+        ixs.push(system_instruction::transfer(
+            &payer,
+            &transfer.address,
+            transfer.lamports,
+        ));
+    }
+    ixs
+}
+
+fn transfer_cu_limit(transfers: &[Transfer]) -> u32 {
+    CU_PER_TRANSFER
+        .saturating_mul(transfers.len() as u32)
+        .saturating_add(CU_COMPUTE_BUDGET)
+}
@@ -0,0 +1,172 @@
+use futures::future::join_all;
+use ore::{state::Proof, utils::AccountDeserialize};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::{
+    fleet::load_fleet,
+    table::Table,
+    utils::{
+        amount_u64_to_f64, format_timestamp, get_last_proof_activity,
+        get_multiple_accounts_batched, lamports_to_sol, parse_token_amount, proof_pubkey,
+    },
+    FleetStatusArgs, Miner, OutputFormat,
+};
+
+#[derive(Serialize)]
+struct FleetStatusRow {
+    authority: String,
+    registered: bool,
+    claimable_rewards: f64,
+    wallet_ore: f64,
+    sol: f64,
+    total_hashes: u64,
+    total_rewards: f64,
+    last_hash_at: Option<i64>,
+}
+
+#[derive(Default, Serialize)]
+struct FleetStatusTotals {
+    miners: usize,
+    registered: usize,
+    claimable_rewards: f64,
+    wallet_ore: f64,
+    sol: f64,
+    total_hashes: u64,
+    total_rewards: f64,
+    last_hash_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct FleetStatus {
+    miners: Vec<FleetStatusRow>,
+    totals: FleetStatusTotals,
+}
+
+impl Miner {
+    pub async fn fleet_status(&self, args: FleetStatusArgs) {
+        let fleet = match load_fleet(&args.keys) {
+            Ok(fleet) => fleet,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            }
+        };
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+
+        // Fetch wallets, proofs, and token accounts in as few requests as possible
+        let mut addresses = vec![];
+        for key in fleet.iter() {
+            addresses.push(key.pubkey);
+            addresses.push(proof_pubkey(key.pubkey));
+            addresses.push(spl_associated_token_account::get_associated_token_address(
+                &key.pubkey,
+                &ore::MINT_ADDRESS,
+            ));
+        }
+        let accounts = match get_multiple_accounts_batched(&client, &addresses).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                log::error!("Error fetching accounts: {:?}", err);
+                return;
+            }
+        };
+        let last_hash_ats = join_all(
+            fleet
+                .iter()
+                .map(|key| get_last_proof_activity(&client, proof_pubkey(key.pubkey))),
+        )
+        .await;
+
+        // Build rows
+        let mut totals = FleetStatusTotals {
+            miners: fleet.len(),
+            ..Default::default()
+        };
+        let mut rows = vec![];
+        for (i, key) in fleet.iter().enumerate() {
+            let wallet = &accounts[i * 3];
+            let proof = accounts[i * 3 + 1]
+                .as_ref()
+                .and_then(|account| Proof::try_from_bytes(&account.data).ok().copied());
+            let token_account = &accounts[i * 3 + 2];
+            let row = FleetStatusRow {
+                authority: key.pubkey.to_string(),
+                registered: proof.is_some(),
+                claimable_rewards: amount_u64_to_f64(proof.map_or(0, |p| p.claimable_rewards)),
+                wallet_ore: amount_u64_to_f64(parse_token_amount(token_account)),
+                sol: lamports_to_sol(wallet.as_ref().map_or(0, |a| a.lamports)),
+                total_hashes: proof.map_or(0, |p| p.total_hashes),
+                total_rewards: amount_u64_to_f64(proof.map_or(0, |p| p.total_rewards)),
+                last_hash_at: last_hash_ats[i],
+            };
+            totals.registered += row.registered as usize;
+            totals.claimable_rewards += row.claimable_rewards;
+            totals.wallet_ore += row.wallet_ore;
+            totals.sol += row.sol;
+            totals.total_hashes += row.total_hashes;
+            totals.total_rewards += row.total_rewards;
+            totals.last_hash_at = totals.last_hash_at.max(row.last_hash_at);
+            rows.push(row);
+        }
+
+        // Print
+        match args.format {
+            OutputFormat::Json => {
+                let status = FleetStatus {
+                    miners: rows,
+                    totals,
+                };
+                println!("{}", serde_json::to_string_pretty(&status).unwrap());
+            }
+            OutputFormat::Table | OutputFormat::Csv => {
+                let mut table = Table::new(&[
+                    "Authority",
+                    "Claimable (ORE)",
+                    "Wallet (ORE)",
+                    "SOL",
+                    "Total hashes",
+                    "Total rewards (ORE)",
+                    "Last hash",
+                ]);
+                for row in rows.iter() {
+                    let last_hash_at = match row.last_hash_at {
+                        Some(t) => format_timestamp(t),
+                        None if row.registered => "-".to_string(),
+                        None => "Not registered".to_string(),
+                    };
+                    table.add_row(vec![
+                        row.authority.clone(),
+                        format!("{:.9}", row.claimable_rewards),
+                        format!("{:.9}", row.wallet_ore),
+                        format!("{:.9}", row.sol),
+                        row.total_hashes.to_string(),
+                        format!("{:.9}", row.total_rewards),
+                        last_hash_at,
+                    ]);
+                }
+                table.add_row(vec![
+                    format!(
+                        "Total ({} of {} registered)",
+                        totals.registered, totals.miners
+                    ),
+                    format!("{:.9}", totals.claimable_rewards),
+                    format!("{:.9}", totals.wallet_ore),
+                    format!("{:.9}", totals.sol),
+                    totals.total_hashes.to_string(),
+                    format!("{:.9}", totals.total_rewards),
+                    totals
+                        .last_hash_at
+                        .map_or("-".to_string(), format_timestamp),
+                ]);
+                if let OutputFormat::Csv = args.format {
+                    table.print_csv();
+                } else {
+                    table.print();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection};
+
+use crate::utils::state_dir;
+
+/// A solution found by `mine`, recorded once its transaction lands.
+pub struct SolutionRecord {
+    pub timestamp: i64,
+    pub authority: String,
+    pub challenge: String,
+    pub hash: String,
+    pub nonce: u64,
+    pub hashes: u64,
+    pub solve_secs: f64,
+    pub bus: u64,
+    pub transactions_sent: u64,
+    pub fee_lamports: u64,
+    pub signature: String,
+    pub landing_secs: f64,
+    pub ore_earned: u64,
+}
+
+/// A transaction sent by `mine`, or the outcome of a solution or session which ended without
+/// one landing.
+pub struct AttemptRecord {
+    pub timestamp: i64,
+    /// `mine`, `reset`, `solution` or `session`.
+    pub kind: &'static str,
+    /// `landed` or `failed` for transactions, otherwise `held`, `interrupted` or `abandoned`.
+    pub outcome: &'static str,
+    pub signature: Option<String>,
+    pub bus: Option<u64>,
+    pub fee_lamports: u64,
+    pub error: Option<String>,
+}
+
+impl AttemptRecord {
+    pub fn new(kind: &'static str, outcome: &'static str) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            kind,
+            outcome,
+            signature: None,
+            bus: None,
+            fee_lamports: 0,
+            error: None,
+        }
+    }
+}
+
+/// The local record of mining activity, kept in a SQLite database in the state directory.
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    pub fn path() -> PathBuf {
+        state_dir().join("history.db")
+    }
+
+    pub fn open() -> rusqlite::Result<Self> {
+        let conn = Connection::open(Self::path())?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS solutions (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                authority TEXT NOT NULL,
+                challenge TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                nonce TEXT NOT NULL,
+                hashes INTEGER NOT NULL,
+                solve_secs REAL NOT NULL,
+                bus INTEGER NOT NULL,
+                transactions_sent INTEGER NOT NULL,
+                fee_lamports INTEGER NOT NULL,
+                signature TEXT NOT NULL,
+                landing_secs REAL NOT NULL,
+                ore_earned INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS solutions_timestamp ON solutions (timestamp);
+            CREATE TABLE IF NOT EXISTS attempts (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                authority TEXT NOT NULL,
+                challenge TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                signature TEXT,
+                bus INTEGER,
+                fee_lamports INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS attempts_timestamp ON attempts (timestamp);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, record: &SolutionRecord) {
+        let result = self.conn.execute(
+            "INSERT INTO solutions (
+                timestamp, authority, challenge, hash, nonce, hashes, solve_secs, bus,
+                transactions_sent, fee_lamports, signature, landing_secs, ore_earned
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                record.timestamp,
+                record.authority,
+                record.challenge,
+                record.hash,
+                record.nonce.to_string(),
+                record.hashes as i64,
+                record.solve_secs,
+                record.bus as i64,
+                record.transactions_sent as i64,
+                record.fee_lamports as i64,
+                record.signature,
+                record.landing_secs,
+                record.ore_earned as i64,
+            ],
+        );
+        if let Err(err) = result {
+            log::warn!("Failed to record mining history: {}", err);
+        }
+    }
+
+    pub fn record_attempt(&self, authority: &str, challenge: &str, attempt: &AttemptRecord) {
+        let result = self.conn.execute(
+            "INSERT INTO attempts (
+                timestamp, authority, challenge, kind, outcome, signature, bus, fee_lamports, error
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                attempt.timestamp,
+                authority,
+                challenge,
+                attempt.kind,
+                attempt.outcome,
+                attempt.signature,
+                attempt.bus.map(|bus| bus as i64),
+                attempt.fee_lamports as i64,
+                attempt.error,
+            ],
+        );
+        if let Err(err) = result {
+            log::warn!("Failed to record mining history: {}", err);
+        }
+    }
+
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::VecDeque,
+    io::{stderr, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{SecondsFormat, Utc};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+use crate::LogFormat;
+
+// Whether terminal control sequences may be written
+static TUI: AtomicBool = AtomicBool::new(false);
+
+// Whether log records are written as JSON
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// Recent log lines, kept instead of writing to stderr while the dashboard owns the terminal
+static CAPTURED: Mutex<Option<VecDeque<String>>> = Mutex::new(None);
+
+// The number of captured log lines to keep
+const CAPTURE_LINES: usize = 100;
+
+/// Writes log records to stderr, as plain text or one JSON object per line.
+struct Logger {
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies are only heard from when something goes wrong
+        metadata.level() <= log::max_level()
+            && (metadata.target().starts_with(env!("CARGO_BIN_NAME"))
+                || metadata.level() <= Level::Warn)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Some(captured) = CAPTURED.lock().unwrap().as_mut() {
+            captured.push_back(format!(
+                "{} {:<5} {}",
+                Utc::now().format("%H:%M:%S"),
+                record.level(),
+                record.args()
+            ));
+            if captured.len() > CAPTURE_LINES {
+                captured.pop_front();
+            }
+            return;
+        }
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let line = match self.format {
+            LogFormat::Json => json!({
+                "timestamp": timestamp,
+                "level": record.level().as_str().to_lowercase(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            })
+            .to_string(),
+            LogFormat::Text if tui() => match record.level() {
+                // Overwrite any progress line
+                Level::Info => format!("\r\x1b[K{}", record.args()),
+                level => format!("\r\x1b[K{}: {}", level, record.args()),
+            },
+            LogFormat::Text => format!("{} {:<5} {}", timestamp, record.level(), record.args()),
+        };
+        writeln!(stderr().lock(), "{}", line).ok();
+    }
+
+    fn flush(&self) {
+        stderr().flush().ok();
+    }
+}
+
+/// Installs the logger. Each `verbose` raises the level from info towards trace, and each
+/// `quiet` lowers it towards error. The terminal UI is only used for text logs on a terminal.
+pub fn init(verbose: u8, quiet: u8, format: LogFormat, no_tui: bool) {
+    let level = match 2 + verbose as i16 - quiet as i16 {
+        i16::MIN..=-1 => LevelFilter::Off,
+        0 => LevelFilter::Error,
+        1 => LevelFilter::Warn,
+        2 => LevelFilter::Info,
+        3 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    let tui = !no_tui && matches!(format, LogFormat::Text) && stderr().is_terminal();
+    TUI.store(tui, Ordering::Relaxed);
+    JSON.store(matches!(format, LogFormat::Json), Ordering::Relaxed);
+    log::set_boxed_logger(Box::new(Logger { format })).expect("Failed to install logger");
+    log::set_max_level(level);
+}
+
+/// Returns true if terminal control sequences may be written.
+pub fn tui() -> bool {
+    TUI.load(Ordering::Relaxed)
+}
+
+/// Returns true if log records are written as JSON.
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Starts or stops keeping log lines in memory instead of writing them to stderr.
+pub fn capture(enabled: bool) {
+    *CAPTURED.lock().unwrap() = enabled.then(VecDeque::new);
+}
+
+/// Returns up to `count` of the most recently captured log lines, oldest first.
+pub fn captured(count: usize) -> Vec<String> {
+    match CAPTURED.lock().unwrap().as_ref() {
+        Some(captured) => captured
+            .iter()
+            .skip(captured.len().saturating_sub(count))
+            .cloned()
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn capturing() -> bool {
+    CAPTURED.lock().unwrap().is_some()
+}
+
+/// Clears the screen and scrollback buffer, if the terminal UI is enabled.
+pub fn clear_screen() {
+    if tui() && !capturing() {
+        stderr().write_all(b"\x1b[2J\x1b[3J\x1b[H").ok();
+    }
+}
+
+/// Overwrites the current line with `line`, if the terminal UI is enabled.
+pub fn progress(line: &str) {
+    if tui() && !capturing() {
+        stderr()
+            .write_all(format!("\r\x1b[K{}", line).as_bytes())
+            .ok();
+    }
+}
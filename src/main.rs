@@ -1,24 +1,53 @@
 mod balance;
 mod busses;
 mod claim;
+mod cpu;
 mod cu_limits;
+mod dashboard;
+mod epoch;
+mod events;
+mod external_hasher;
+mod fleet;
+mod fleet_claim;
+mod fleet_fund;
+mod fleet_status;
+mod history;
 #[cfg(feature = "admin")]
 mod initialize;
+mod logger;
+mod metrics;
 mod mine;
+mod nonces;
+mod pool;
+mod pool_ledger;
+mod pool_serve;
+mod pool_work;
+mod price;
+mod profitability;
+mod progress;
+mod proof;
 mod register;
 mod rewards;
+mod schedule;
 mod send_and_confirm;
+mod session;
+mod stats;
+mod table;
 mod treasury;
 #[cfg(feature = "admin")]
 mod update_admin;
 #[cfg(feature = "admin")]
 mod update_difficulty;
 mod utils;
+mod wallet;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use clap::{command, Parser, Subcommand};
+use clap::{command, ArgAction, Parser, Subcommand, ValueEnum};
+use price::{parse_price_source, PriceSource};
+use schedule::{parse_window, Window};
 use solana_sdk::signature::{read_keypair_file, Keypair};
+use utils::{parse_core_list, parse_duration, parse_hashrate};
 
 struct Miner {
     pub keypair_filepath: Option<String>,
@@ -52,6 +81,57 @@ struct Args {
     )]
     priority_fee: u64,
 
+    #[arg(
+        short,
+        long,
+        action = ArgAction::Count,
+        global = true,
+        help = "Log more detail, repeat for even more"
+    )]
+    verbose: u8,
+
+    #[arg(
+        short,
+        long,
+        action = ArgAction::Count,
+        global = true,
+        help = "Log less detail, repeat for even less"
+    )]
+    quiet: u8,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Log format",
+        default_value = "text",
+        global = true
+    )]
+    log_format: LogFormat,
+
+    #[arg(
+        long,
+        help = "Never clear the screen or redraw progress, e.g. when logging to a file or journald",
+        global = true
+    )]
+    no_tui: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Write machine-readable events in this format",
+        global = true
+    )]
+    events: Option<EventFormat>,
+
+    #[arg(
+        long,
+        value_name = "TARGET",
+        help = "Where to write events: - for stdout, a file path, or unix:<socket path>",
+        default_value = "-",
+        global = true
+    )]
+    events_to: String,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -65,7 +145,7 @@ enum Commands {
     Busses(BussesArgs),
 
     #[command(about = "Mine Ore using local compute")]
-    Mine(MineArgs),
+    Mine(Box<MineArgs>),
 
     #[command(about = "Claim available mining rewards")]
     Claim(ClaimArgs),
@@ -76,6 +156,29 @@ enum Commands {
     #[command(about = "Fetch the treasury account and balance")]
     Treasury(TreasuryArgs),
 
+    #[command(about = "Show when the epoch resets and how much of its rewards remain")]
+    Epoch(EpochArgs),
+
+    #[command(about = "Summarize local mining history")]
+    Stats(StatsArgs),
+
+    #[command(about = "Inspect a proof account, and optionally watch its hash advance")]
+    Proof(ProofArgs),
+
+    #[command(about = "Summarize the balances, token accounts, proof and fee runway of a wallet")]
+    Wallet(WalletArgs),
+
+    #[command(
+        about = "Project mining returns at a hashrate from the current difficulty and rewards"
+    )]
+    Profitability(ProfitabilityArgs),
+
+    #[command(about = "Manage many miners at once")]
+    Fleet(FleetArgs),
+
+    #[command(about = "Pool hashpower from many machines behind one miner")]
+    Pool(PoolArgs),
+
     #[cfg(feature = "admin")]
     #[command(about = "Initialize the program")]
     Initialize(InitializeArgs),
@@ -100,7 +203,89 @@ struct BalanceArgs {
 }
 
 #[derive(Parser, Debug)]
-struct BussesArgs {}
+struct BussesArgs {
+    #[arg(
+        long,
+        value_name = "ORDER",
+        help = "Sort busses by id, or by rewards with the most first",
+        default_value = "id"
+    )]
+    sort: BusSort,
+
+    #[arg(long, help = "Reverse the sort order")]
+    reverse: bool,
+
+    #[arg(
+        long,
+        value_name = "INTERVAL",
+        help = "Keep refreshing at this interval, e.g. 10s, and show what each bus spent since the last refresh",
+        value_parser = parse_duration
+    )]
+    watch: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BusSort {
+    Id,
+    Rewards,
+}
+
+#[derive(Parser, Debug)]
+struct ProofArgs {
+    #[arg(
+        value_name = "ADDRESS",
+        help = "The authority or proof address. Defaults to the signer."
+    )]
+    address: Option<String>,
+
+    #[arg(
+        long,
+        help = "Keep polling and print a line each time the hash advances"
+    )]
+    watch: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "How often to poll with --watch",
+        default_value = "5s",
+        value_parser = parse_duration
+    )]
+    interval: Duration,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+struct WalletArgs {
+    #[arg(
+        value_name = "ADDRESS",
+        help = "The address or keypair file of the wallet. Defaults to the signer."
+    )]
+    address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
 
 #[derive(Parser, Debug)]
 struct RewardsArgs {
@@ -122,11 +307,178 @@ struct MineArgs {
         default_value = "1"
     )]
     threads: u64,
+
+    #[arg(
+        long,
+        value_name = "PROGRAM",
+        help = "Program to run as an external hasher, which replaces the local hashing threads"
+    )]
+    external_hasher: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ARG",
+        help = "Argument to pass to the external hasher. Repeat for each argument.",
+        requires = "external_hasher",
+        allow_hyphen_values = true
+    )]
+    external_hasher_arg: Vec<String>,
+
+    #[arg(long, value_name = "ORE", help = "Stop after earning this much ORE")]
+    until_ore: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Stop after finding this many solutions"
+    )]
+    max_solutions: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Stop after mining for this long, e.g. 90m or 2h30m",
+        value_parser = parse_duration
+    )]
+    duration: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Stop once the SOL balance has dropped by this much since mining started"
+    )]
+    max_sol_spent: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Write the session summary to this file as JSON when mining stops"
+    )]
+    summary_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Serve Prometheus metrics on this address, e.g. 127.0.0.1:9100"
+    )]
+    metrics_addr: Option<String>,
+
+    #[arg(
+        long,
+        help = "Show a full-screen dashboard with live hashrate, busses, balances and recent transactions"
+    )]
+    dashboard: bool,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Index of this machine among machines mining the same authority, from 0",
+        requires = "worker_count"
+    )]
+    worker_id: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of machines mining the same authority, to split the nonce space between",
+        requires = "worker_id",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    worker_count: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "LIST",
+        help = "Pin hashing threads to these CPU cores, in order, e.g. 0,2,4-7",
+        value_parser = parse_core_list
+    )]
+    // Fully qualified so clap parses the list as a single value
+    cores: Option<::std::vec::Vec<usize>>,
+
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Run hashing threads at this nice level, from -20 (highest priority) to 19 (lowest)",
+        value_parser = clap::value_parser!(i32).range(-20..=19)
+    )]
+    nice: Option<i32>,
+
+    #[arg(
+        long,
+        help = "Benchmark thread counts up to the number of cores and mine with the fastest",
+        conflicts_with = "external_hasher"
+    )]
+    auto_threads: bool,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Limit each hashing thread to this share of CPU time",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    max_cpu: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "WINDOW",
+        help = "Only hash during this window in local time, e.g. 22:00-06:00 or \"mon-fri 18:00-08:00\". Can be repeated. Found hashes are still submitted outside it.",
+        value_parser = parse_window
+    )]
+    schedule: Vec<Window>,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Claim rewards whenever the claimable amount reaches this much ORE"
+    )]
+    auto_claim_threshold: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Wallet or ORE token account to receive automatic claims. Defaults to the signer's ORE account.",
+        requires = "auto_claim_threshold"
+    )]
+    claim_to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SOL|FILEPATH|URL",
+        help = "The price of one ORE in SOL, or a file or http:// URL to read it from. https:// is not supported, use a file kept up to date by another tool instead.",
+        value_parser = parse_price_source
+    )]
+    ore_price: Option<PriceSource>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Hold hashes, or lower the priority fee, while the reward minus the fee is worth less than this much SOL",
+        requires = "ore_price"
+    )]
+    min_profit: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Keep at least this much SOL, pausing mining and holding hashes when fees would go below it"
+    )]
+    min_sol: Option<f64>,
 }
 
 #[derive(Parser, Debug)]
 struct TreasuryArgs {}
 
+#[derive(Parser, Debug)]
+struct EpochArgs {
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
+
 #[derive(Parser, Debug)]
 struct ClaimArgs {
     #[arg(
@@ -144,6 +496,323 @@ struct ClaimArgs {
     beneficiary: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+struct FleetArgs {
+    #[command(subcommand)]
+    command: FleetCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum FleetCommands {
+    #[command(about = "Fetch the rewards and balances of every miner in the fleet")]
+    Status(FleetStatusArgs),
+
+    #[command(about = "Claim the rewards of every miner in the fleet into one token account")]
+    Claim(FleetClaimArgs),
+
+    #[command(about = "Top up the SOL balance of every miner in the fleet")]
+    Fund(FleetFundArgs),
+}
+
+#[derive(Parser, Debug)]
+struct FleetStatusArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Directory of keypair files, or a file listing keypair filepaths or addresses"
+    )]
+    keys: String,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+struct FleetClaimArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Directory of keypair files, or a file listing keypair filepaths"
+    )]
+    keys: String,
+
+    #[arg(
+        long,
+        value_name = "OWNER_ADDRESS",
+        help = "Owner of the ORE token account to receive all rewards"
+    )]
+    to: String,
+
+    #[arg(
+        long,
+        help = "Print the claim summary without sending any transactions"
+    )]
+    dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+struct FleetFundArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Directory of keypair files, or a file listing keypair filepaths or addresses"
+    )]
+    keys: String,
+
+    #[arg(
+        long,
+        value_name = "KEYPAIR_FILEPATH",
+        help = "Keypair to fund the fleet from. Defaults to --keypair."
+    )]
+    from: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "The SOL balance to top each key up to"
+    )]
+    target: f64,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Only top up keys whose balance is below this amount. Defaults to --target."
+    )]
+    floor: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Keep running and top up keys whenever they fall below the floor"
+    )]
+    daemon: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Seconds between balance checks in daemon mode",
+        default_value = "60"
+    )]
+    interval: u64,
+
+    #[arg(
+        long,
+        help = "Print the funding summary without sending any transactions"
+    )]
+    dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+struct PoolArgs {
+    #[command(subcommand)]
+    command: PoolCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum PoolCommands {
+    #[command(about = "Run a pool server which hands out work and submits solutions")]
+    Serve(PoolServeArgs),
+
+    #[command(about = "Mine for a pool server using local compute")]
+    Work(PoolWorkArgs),
+}
+
+#[derive(Parser, Debug)]
+struct PoolServeArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address to listen for workers on",
+        default_value = "127.0.0.1:9000"
+    )]
+    listen: String,
+
+    #[arg(
+        long,
+        help = "Allow listening on a non-loopback address. Workers are not authenticated, so only use this on a trusted network."
+    )]
+    public: bool,
+
+    #[arg(
+        long,
+        value_name = "NONCES",
+        help = "The number of nonces to hand out to a worker at a time",
+        default_value = "1000000000"
+    )]
+    range_size: u64,
+
+    #[arg(
+        long,
+        value_name = "BITS",
+        help = "Leading zero bits a hash needs to count as a share",
+        default_value = "20"
+    )]
+    share_bits: u32,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Filepath to persist the share ledger to",
+        default_value = "pool-ledger.json"
+    )]
+    ledger: String,
+
+    #[arg(
+        long,
+        value_name = "METHOD",
+        help = "How to split rewards between workers",
+        default_value = "pplns"
+    )]
+    payout_method: PayoutMethod,
+
+    #[arg(
+        long,
+        value_name = "SHARES",
+        help = "The number of recent shares to split rewards across with PPLNS",
+        default_value = "10000"
+    )]
+    pplns_window: usize,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "The minimum balance a worker is owed before it is paid",
+        default_value = "0.1"
+    )]
+    payout_threshold: f64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Seconds between payouts",
+        default_value = "3600"
+    )]
+    payout_interval: u64,
+}
+
+#[derive(Parser, Debug)]
+struct PoolWorkArgs {
+    #[arg(long, value_name = "ADDRESS", help = "Address of the pool server")]
+    server: String,
+
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of threads to dedicate to mining",
+        default_value = "1"
+    )]
+    threads: u64,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Name to identify this worker to the server",
+        default_value = "worker"
+    )]
+    name: String,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address to receive this worker's share of pool rewards"
+    )]
+    payout: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct StatsArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Only include solutions mined by this authority"
+    )]
+    authority: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DAYS",
+        help = "Number of days of history to summarize",
+        default_value = "7"
+    )]
+    days: u64,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+struct ProfitabilityArgs {
+    #[arg(
+        long,
+        value_name = "HASHRATE",
+        help = "The hashrate to project, e.g. 850000 or 2.5M. Defaults to a benchmark of this machine.",
+        value_parser = parse_hashrate
+    )]
+    hashrate: Option<f64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of threads to benchmark. Defaults to the number of cores.",
+        conflicts_with = "hashrate"
+    )]
+    threads: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SOL|FILEPATH|URL",
+        help = "The price of one ORE in SOL, or a file or http:// URL to read it from. https:// is not supported, use a file kept up to date by another tool instead.",
+        value_parser = parse_price_source
+    )]
+    ore_price: Option<PriceSource>,
+
+    #[arg(long, value_name = "USD", help = "The price of one SOL in USD")]
+    sol_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "table"
+    )]
+    format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EventFormat {
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PayoutMethod {
+    Pplns,
+    Proportional,
+}
+
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 struct InitializeArgs {}
@@ -162,6 +831,13 @@ struct UpdateDifficultyArgs {}
 async fn main() {
     // Initialize miner.
     let args = Args::parse();
+    logger::init(args.verbose, args.quiet, args.log_format, args.no_tui);
+    if let Some(EventFormat::Ndjson) = args.events {
+        if let Err(err) = events::init(&args.events_to) {
+            log::error!("{}", err);
+            return;
+        }
+    }
     let cluster = args.rpc;
     let miner = Arc::new(Miner::new(cluster.clone(), args.priority_fee, args.keypair));
 
@@ -170,8 +846,8 @@ async fn main() {
         Commands::Balance(args) => {
             miner.balance(args.address).await;
         }
-        Commands::Busses(_) => {
-            miner.busses().await;
+        Commands::Busses(args) => {
+            miner.busses(args).await;
         }
         Commands::Rewards(args) => {
             miner.rewards(args.address).await;
@@ -179,12 +855,46 @@ async fn main() {
         Commands::Treasury(_) => {
             miner.treasury().await;
         }
+        Commands::Epoch(args) => {
+            miner.epoch(args).await;
+        }
         Commands::Mine(args) => {
-            miner.mine(args.threads).await;
+            miner.mine(*args).await;
         }
         Commands::Claim(args) => {
             miner.claim(cluster, args.beneficiary, args.amount).await;
         }
+        Commands::Stats(args) => {
+            miner.stats(args).await;
+        }
+        Commands::Proof(args) => {
+            miner.proof(args).await;
+        }
+        Commands::Wallet(args) => {
+            miner.wallet(args).await;
+        }
+        Commands::Profitability(args) => {
+            miner.profitability(args).await;
+        }
+        Commands::Fleet(args) => match args.command {
+            FleetCommands::Status(args) => {
+                miner.fleet_status(args).await;
+            }
+            FleetCommands::Claim(args) => {
+                miner.fleet_claim(args).await;
+            }
+            FleetCommands::Fund(args) => {
+                miner.fleet_fund(args).await;
+            }
+        },
+        Commands::Pool(args) => match args.command {
+            PoolCommands::Serve(args) => {
+                miner.pool_serve(args).await;
+            }
+            PoolCommands::Work(args) => {
+                miner.pool_work(args).await;
+            }
+        },
         #[cfg(feature = "admin")]
         Commands::Initialize(_) => {
             miner.initialize().await;
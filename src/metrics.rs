@@ -0,0 +1,298 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ore::EPOCH_DURATION;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    mine::HashCounters,
+    utils::{amount_u64_to_f64, get_miner_accounts, lamports_to_sol},
+};
+
+// How often to sample the hashrate and refresh on-chain gauges
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Miner state exported in the Prometheus text format.
+pub struct Metrics {
+    counters: Arc<HashCounters>,
+    state: Mutex<MetricsState>,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    hashrate: Vec<f64>,
+    solutions: u64,
+    transactions_sent: u64,
+    transactions_landed: u64,
+    transactions_failed: BTreeMap<&'static str, u64>,
+    priority_fee: u64,
+    claimable_rewards: u64,
+    wallet_ore: u64,
+    wallet_sol: u64,
+    bus_rewards: Vec<u64>,
+    epoch_reset_at: i64,
+}
+
+impl Metrics {
+    pub fn new(counters: Arc<HashCounters>, priority_fee: u64) -> Self {
+        Self {
+            counters,
+            state: Mutex::new(MetricsState {
+                priority_fee,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn record_solution(&self) {
+        self.state.lock().unwrap().solutions += 1;
+    }
+
+    /// Records the attempts of a submission, whether or not it landed, and the priority fee it was
+    /// sent with.
+    pub fn record_submission(
+        &self,
+        transactions_sent: u64,
+        failures: &[&'static str],
+        landed: bool,
+        priority_fee: u64,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.transactions_sent += transactions_sent;
+        state.transactions_landed += landed as u64;
+        state.priority_fee = priority_fee;
+        for class in failures {
+            *state.transactions_failed.entry(class).or_default() += 1;
+        }
+    }
+
+    /// Serves metrics over HTTP on `addr`, and keeps them up to date for `authority`.
+    pub async fn serve(self: Arc<Self>, addr: String, cluster: String, authority: Pubkey) {
+        let listener = match TcpListener::bind(&addr).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::error!("Failed to serve metrics on {}: {}", addr, err);
+                return;
+            }
+        };
+        tokio::spawn(self.clone().refresh(cluster, authority));
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let metrics = self.clone();
+            tokio::spawn(async move {
+                metrics.respond(stream).await.ok();
+            });
+        }
+    }
+
+    async fn respond(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut request = vec![0u8; 1024];
+        let len = stream.read(&mut request).await?;
+        let request = String::from_utf8_lossy(&request[..len]);
+        let path = request.split_whitespace().nth(1).unwrap_or("/");
+        let response = if path.eq("/metrics") {
+            let body = self.render();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        } else {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Samples the hashrate and fetches balances, busses and the treasury on an interval.
+    async fn refresh(self: Arc<Self>, cluster: String, authority: Pubkey) {
+        let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
+        let mut last_sample = (Instant::now(), self.counters.per_thread());
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            // Hashrate since the last sample
+            let sample = (Instant::now(), self.counters.per_thread());
+            let secs = sample
+                .0
+                .duration_since(last_sample.0)
+                .as_secs_f64()
+                .max(1.0);
+            self.state.lock().unwrap().hashrate = sample
+                .1
+                .iter()
+                .zip(last_sample.1.iter())
+                .map(|(now, before)| now.saturating_sub(*before) as f64 / secs)
+                .collect();
+            last_sample = sample;
+
+            // On-chain state
+            let Ok(accounts) = get_miner_accounts(&client, authority).await else {
+                continue;
+            };
+            let mut state = self.state.lock().unwrap();
+            state.wallet_sol = accounts.wallet_sol;
+            state.wallet_ore = accounts.wallet_ore;
+            if let Some(proof) = accounts.proof {
+                state.claimable_rewards = proof.claimable_rewards;
+            }
+            if let Some(treasury) = accounts.treasury {
+                state.epoch_reset_at = treasury.last_reset_at.saturating_add(EPOCH_DURATION);
+            }
+            state.bus_rewards = accounts.bus_rewards;
+        }
+    }
+
+    fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+        let mut metric = |name: &str, help: &str, kind: &str, samples: Vec<(String, String)>| {
+            writeln!(out, "# HELP {} {}", name, help).ok();
+            writeln!(out, "# TYPE {} {}", name, kind).ok();
+            for (labels, value) in samples {
+                writeln!(out, "{}{} {}", name, labels, value).ok();
+            }
+        };
+        metric(
+            "ore_hashrate",
+            "Hashes per second across all threads",
+            "gauge",
+            vec![(
+                String::new(),
+                state.hashrate.iter().sum::<f64>().to_string(),
+            )],
+        );
+        metric(
+            "ore_thread_hashrate",
+            "Hashes per second of each mining thread",
+            "gauge",
+            state
+                .hashrate
+                .iter()
+                .enumerate()
+                .map(|(i, rate)| (format!("{{thread=\"{}\"}}", i), rate.to_string()))
+                .collect(),
+        );
+        metric(
+            "ore_hashes_total",
+            "Hashes computed this session",
+            "counter",
+            vec![(String::new(), self.counters.total().to_string())],
+        );
+        metric(
+            "ore_solutions_total",
+            "Valid hashes found this session",
+            "counter",
+            vec![(String::new(), state.solutions.to_string())],
+        );
+        metric(
+            "ore_transactions_sent_total",
+            "Mine transactions sent this session",
+            "counter",
+            vec![(String::new(), state.transactions_sent.to_string())],
+        );
+        metric(
+            "ore_transactions_landed_total",
+            "Mine transactions landed this session",
+            "counter",
+            vec![(String::new(), state.transactions_landed.to_string())],
+        );
+        metric(
+            "ore_transactions_failed_total",
+            "Mine transactions which failed to land, by error class",
+            "counter",
+            state
+                .transactions_failed
+                .iter()
+                .map(|(class, count)| (format!("{{class=\"{}\"}}", class), count.to_string()))
+                .collect(),
+        );
+        metric(
+            "ore_priority_fee_microlamports",
+            "Priority fee paid per compute unit",
+            "gauge",
+            vec![(String::new(), state.priority_fee.to_string())],
+        );
+        metric(
+            "ore_claimable_rewards",
+            "Unclaimed mining rewards in ORE",
+            "gauge",
+            vec![(
+                String::new(),
+                amount_u64_to_f64(state.claimable_rewards).to_string(),
+            )],
+        );
+        metric(
+            "ore_wallet_ore",
+            "Wallet ORE balance",
+            "gauge",
+            vec![(
+                String::new(),
+                amount_u64_to_f64(state.wallet_ore).to_string(),
+            )],
+        );
+        metric(
+            "ore_wallet_sol",
+            "Wallet SOL balance",
+            "gauge",
+            vec![(String::new(), lamports_to_sol(state.wallet_sol).to_string())],
+        );
+        metric(
+            "ore_bus_rewards",
+            "Rewards left on each bus in ORE",
+            "gauge",
+            state
+                .bus_rewards
+                .iter()
+                .enumerate()
+                .map(|(i, rewards)| {
+                    (
+                        format!("{{bus=\"{}\"}}", i),
+                        amount_u64_to_f64(*rewards).to_string(),
+                    )
+                })
+                .collect(),
+        );
+        let now = chrono::Utc::now().timestamp();
+        metric(
+            "ore_epoch_reset_seconds",
+            "Seconds until the epoch can be reset",
+            "gauge",
+            vec![(
+                String::new(),
+                state.epoch_reset_at.saturating_sub(now).max(0).to_string(),
+            )],
+        );
+        out
+    }
+}
+
+/// Groups transaction errors into a small set of classes for metric labels.
+pub fn error_class(err: &ClientError) -> &'static str {
+    match &err.kind {
+        ClientErrorKind::Io(_) => "io",
+        ClientErrorKind::Reqwest(_) => "network",
+        ClientErrorKind::RpcError(_) => "rpc",
+        ClientErrorKind::SerdeJson(_) => "serialization",
+        ClientErrorKind::SigningError(_) => "signing",
+        ClientErrorKind::TransactionError(_) => "transaction",
+        ClientErrorKind::Custom(msg) if msg.contains("Insufficient") => "insufficient_funds",
+        ClientErrorKind::Custom(msg) if msg.contains("Max retries") => "not_landed",
+        ClientErrorKind::Custom(_) => "other",
+    }
+}
@@ -1,6 +1,9 @@
 use std::{
-    io::{stdout, Write},
-    sync::{atomic::AtomicBool, Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use ore::{self, state::Bus, BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION};
@@ -10,102 +13,993 @@ use solana_sdk::{
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
     keccak::{hashv, Hash as KeccakHash},
-    signature::Signer,
+    native_token::{sol_to_lamports, LAMPORTS_PER_SOL},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
 };
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
-    cu_limits::{CU_LIMIT_MINE, CU_LIMIT_RESET},
-    utils::{get_clock_account, get_proof, get_treasury},
-    Miner,
+    claim::Claimed,
+    cpu,
+    cu_limits::{CU_LIMIT_CLAIM, CU_LIMIT_MINE, CU_LIMIT_RESET},
+    dashboard::{Dashboard, RecentTransaction},
+    events::{self, Event},
+    external_hasher::{self, ExternalHasher},
+    history::{AttemptRecord, History, SolutionRecord},
+    logger,
+    metrics::{error_class, Metrics},
+    nonces::NonceCursor,
+    price::PriceSource,
+    progress::Progress,
+    schedule::Schedule,
+    session::{PendingSolution, Session},
+    utils::{
+        amount_u64_to_f64, estimate_fee, fee_runway, format_duration, get_clock_account, get_proof,
+        get_treasury, lamports_to_sol,
+    },
+    MineArgs, Miner,
 };
 
 // Odds of being selected to submit a reset tx
 const RESET_ODDS: u64 = 20;
 
+// How often to emit progress events while hashing
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+// How often to check whether a held hash has become profitable to submit
+const PROFIT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// How long the last fetched ORE price is used for while the price source is failing
+const MAX_PRICE_AGE: Duration = Duration::from_secs(5 * 60);
+
+// The number of nonces a hashing thread takes at a time, between checks for a stop request
+const NONCES_PER_CHUNK: u64 = 10_000;
+
+// How long to wait before retrying a failed automatic claim, doubling after each failure
+const CLAIM_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+// The longest to wait between automatic claim attempts
+const MAX_CLAIM_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// How often to check whether a low SOL balance has been topped up
+const BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How a wait for the mining schedule ended.
+enum ScheduleWait {
+    /// The schedule was already open.
+    Open,
+    /// The schedule was closed and has since opened.
+    Reopened,
+    /// Mining stopped before the schedule opened.
+    Stopped,
+}
+
+/// The outcome of submitting a hash.
+pub struct Submission {
+    /// The landed mine transaction, or `None` if submitting stopped before one landed.
+    pub signature: Option<Signature>,
+    pub fees: u64,
+    pub transactions_sent: u64,
+    pub bus: u64,
+    pub landing_secs: f64,
+    pub failures: Vec<&'static str>,
+    pub attempts: Vec<AttemptRecord>,
+    /// Whether submitting stopped because the SOL balance fell to the floor.
+    pub below_floor: bool,
+}
+
 impl Miner {
-    pub async fn mine(&self, threads: u64) {
+    pub async fn mine(&self, mut args: MineArgs) {
         // Register, if needed.
         let signer = self.signer();
+        let authority = signer.pubkey().to_string();
         self.register().await;
-        let mut stdout = stdout();
-        let mut rng = rand::thread_rng();
+
+        // Start external hasher, if provided.
+        let mut external_hasher = match args.external_hasher.as_ref() {
+            Some(program) => match ExternalHasher::spawn(program, &args.external_hasher_arg) {
+                Ok(hasher) => Some(hasher),
+                Err(err) => {
+                    log::error!("{}", err);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        // Pin, throttle and deprioritize hashing threads, then pick the fastest thread count
+        cpu::configure(
+            args.cores.clone().unwrap_or_default(),
+            args.nice,
+            args.max_cpu,
+        );
+        let schedule = Schedule::new(args.schedule.clone());
+        if args.auto_threads {
+            let max_threads = args
+                .cores
+                .as_ref()
+                .map_or(num_cpus::get(), |cores| cores.len()) as u64;
+            args.threads = tokio::task::spawn_blocking(move || cpu::benchmark_threads(max_threads))
+                .await
+                .unwrap();
+        }
+
+        // Resolve the claim address before mining
+        let claim_to = match args.claim_to.as_ref() {
+            Some(claim_to) => match self.resolve_beneficiary(claim_to).await {
+                Ok(beneficiary) => Some(beneficiary.to_string()),
+                Err(err) => {
+                    log::error!("{}", err);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        // Stop hashing on the first Ctrl-C or SIGTERM, or once the session duration has elapsed
+        let mut session = Session::new();
+        let stop = Arc::new(AtomicBool::new(false));
+        tokio::spawn(handle_signals(stop.clone()));
+        if let Some(duration) = args.duration {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(duration);
+                stop.store(true, Ordering::Relaxed);
+            });
+        }
+
+        // Open the local mining history
+        let history = match History::open() {
+            Ok(history) => Some(history),
+            Err(err) => {
+                log::warn!(
+                    "Failed to open mining history, solutions will not be recorded: {}",
+                    err
+                );
+                None
+            }
+        };
+
+        // Serve metrics, if requested. The dashboard can add threads up to the core count.
+        let max_threads = match args.dashboard {
+            true => std::thread::available_parallelism()
+                .map_or(args.threads, |cores| cores.get() as u64)
+                .max(args.threads),
+            false => args.threads,
+        };
+        let counters = Arc::new(HashCounters::new(max_threads));
+        let metrics = args.metrics_addr.as_ref().map(|addr| {
+            let metrics = Arc::new(Metrics::new(counters.clone(), self.priority_fee));
+            tokio::spawn(metrics.clone().serve(
+                addr.clone(),
+                self.cluster.clone(),
+                signer.pubkey(),
+            ));
+            metrics
+        });
+
+        // Report progress, if events are enabled
+        if events::enabled() {
+            events::emit(Event::SessionStart {
+                authority: signer.pubkey().to_string(),
+                threads: args.threads,
+            });
+            tokio::spawn(emit_progress(counters.clone()));
+        }
+
+        // Report hashrate and progress towards a solution
+        let progress = Progress::start(counters.clone());
+
+        // Keep this much SOL in the wallet, pausing and holding hashes below it
+        let floor = args.min_sol.map_or(0, sol_to_lamports);
+
+        // Show the dashboard, if requested
+        let (dashboard, _terminal) = match (args.dashboard, logger::tui()) {
+            (true, true) => match Dashboard::start(
+                self.cluster.clone(),
+                signer.pubkey(),
+                self.priority_fee,
+                counters.clone(),
+                progress.clone(),
+                stop.clone(),
+                args.threads,
+            ) {
+                Ok((dashboard, terminal)) => {
+                    dashboard.update(|state| state.min_lamports = floor);
+                    (Some(dashboard), Some(terminal))
+                }
+                Err(err) => {
+                    log::warn!("{}", err);
+                    (None, None)
+                }
+            },
+            (true, false) => {
+                log::warn!("The dashboard needs an interactive terminal, ignoring --dashboard");
+                (None, None)
+            }
+            (false, _) => (None, None),
+        };
+
+        // Search a random part of the nonce space, or this worker's share of it
+        let worker = args.worker_id.zip(args.worker_count);
+        if let Some((id, count)) = worker {
+            if id >= count {
+                log::error!("Worker id must be less than the worker count");
+                return;
+            }
+        }
+        let mut cursor: Option<NonceCursor> = None;
+
+        // Resume a solution left unsubmitted by a previous session
+        let mut pending = PendingSolution::take(signer.pubkey());
+        let mut last_price: Option<(f64, Instant)> = None;
+        let mut claim_retry_at: Option<Instant> = None;
+        let mut claim_failures = 0;
 
         // Start mining loop
         loop {
             // Fetch account state
             let balance = self.get_ore_display_balance().await;
             let treasury = get_treasury(self.cluster.clone()).await;
-            let proof = get_proof(self.cluster.clone(), signer.pubkey()).await;
+            let mut proof = get_proof(self.cluster.clone(), signer.pubkey()).await;
+            session.update_rewards(proof.total_rewards);
+            if let Some(dashboard) = dashboard.as_ref() {
+                dashboard.update(|state| state.ore_earned = session.ore_earned);
+            }
+            let client =
+                RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+            let sol_balance = client.get_balance(&signer.pubkey()).await.ok();
+            if let Some(sol_balance) = sol_balance {
+                session.update_sol_balance(sol_balance);
+            }
+            if let Some(reason) = session.stop_reason(&args) {
+                log::info!("{}, stopping.", reason);
+                break;
+            }
+
+            // Pause while the SOL balance can't pay for a mine transaction above the floor
+            if let (Some(sol_balance), Some(_)) = (sol_balance, args.min_sol) {
+                let required = floor + estimate_fee(CU_LIMIT_MINE, self.priority_fee);
+                if sol_balance < required {
+                    if !self
+                        .wait_for_min_sol(sol_balance, required, &stop, dashboard.as_deref())
+                        .await
+                    {
+                        log::info!("Interrupted, stopping.");
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            // Claim rewards once they reach the threshold, backing off after failures
+            if let Some(threshold) = args.auto_claim_threshold {
+                if amount_u64_to_f64(proof.claimable_rewards) >= threshold
+                    && claim_retry_at.map_or(true, |at| Instant::now() >= at)
+                {
+                    log::info!("Claimable rewards reached the threshold, claiming...");
+                    match self
+                        .auto_claim(claim_to.clone(), floor, dashboard.as_deref())
+                        .await
+                    {
+                        Some(claimed) => {
+                            session.record_claim(
+                                claimed,
+                                estimate_fee(CU_LIMIT_CLAIM, self.priority_fee),
+                            );
+                            if let Some(dashboard) = dashboard.as_ref() {
+                                dashboard.update(|state| state.fees_spent = session.fees_spent);
+                            }
+                            (claim_retry_at, claim_failures) = (None, 0);
+                            proof = get_proof(self.cluster.clone(), signer.pubkey()).await;
+                        }
+                        None => {
+                            let backoff = CLAIM_RETRY_INTERVAL
+                                .saturating_mul(1 << claim_failures.min(6))
+                                .min(MAX_CLAIM_RETRY_INTERVAL);
+                            claim_failures += 1;
+                            claim_retry_at = Some(Instant::now() + backoff);
+                            log::warn!(
+                                "Automatic claim failed, retrying in {}",
+                                format_duration(backoff)
+                            );
+                        }
+                    }
+                }
+            }
             let rewards =
                 (proof.claimable_rewards as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
             let reward_rate =
                 (treasury.reward_rate as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
-            stdout.write_all(b"\x1b[2J\x1b[3J\x1b[H").ok();
-            println!("Balance: {} ORE", balance);
-            println!("Claimable: {} ORE", rewards);
-            println!("Reward rate: {} ORE", reward_rate);
+            logger::clear_screen();
+            log::info!("Balance: {} ORE", balance);
+            if let Some(sol_balance) = sol_balance {
+                log::info!(
+                    "SOL: {} (~{} mine transactions left)",
+                    lamports_to_sol(sol_balance),
+                    fee_runway(sol_balance.saturating_sub(floor), self.priority_fee)
+                );
+            }
+            log::info!("Claimable: {} ORE", rewards);
+            log::info!("Reward rate: {} ORE", reward_rate);
+            log::info!("Mining for a valid hash...");
+            let challenge = KeccakHash::from(proof.hash);
+            if cursor
+                .as_ref()
+                .map_or(true, |c| c.challenge().ne(&challenge))
+            {
+                cursor = Some(NonceCursor::new(signer.pubkey(), challenge, worker));
+            }
+            let cursor = cursor.as_mut().unwrap();
+            let (nonce_start, nonce_end) = cursor.remaining();
+            let params = HashParams {
+                challenge,
+                authority: signer.pubkey(),
+                difficulty: treasury.difficulty.into(),
+                nonce_start,
+                nonce_end,
+            };
+            events::emit(Event::Challenge {
+                challenge: params.challenge.to_string(),
+                difficulty: params.difficulty.to_string(),
+                reward_rate_ore: reward_rate,
+                claimable_ore: rewards,
+                balance_ore: balance,
+            });
+            progress.start_challenge(params.difficulty);
+            let solve_started_at = Instant::now();
+            let mut hashes = 0;
+            let mut reopened = false;
+            let mut hasher_failed = false;
+            let challenge = params.challenge.to_string();
+            let pending_solution = pending.take().filter(|pending| {
+                let current = pending.challenge.eq(&params.challenge);
+                if let (false, Some(history)) = (current, history.as_ref()) {
+                    history.record_attempt(
+                        &authority,
+                        &pending.challenge.to_string(),
+                        &AttemptRecord::new("solution", "abandoned"),
+                    );
+                }
+                current
+            });
+            let resumed = pending_solution.is_some();
+            let solution = match pending_solution {
+                Some(pending) => {
+                    log::info!("Resuming unsubmitted hash {}", pending.hash);
+                    Some((pending.hash, pending.nonce))
+                }
+                None => {
+                    // Search a block at a time, checkpointing after each
+                    let hashes_before = counters.total();
+                    let mut solution = None;
+                    let mut throttle = cpu::Throttle::new();
+                    while !stop.load(Ordering::Relaxed) {
+                        // Checked between blocks, so hashing stops within a block of the schedule closing
+                        match self
+                            .wait_for_schedule(&schedule, &stop, dashboard.as_deref())
+                            .await
+                        {
+                            ScheduleWait::Open => {}
+                            ScheduleWait::Reopened => {
+                                reopened = true;
+                                break;
+                            }
+                            ScheduleWait::Stopped => break,
+                        }
 
-            // Escape sequence that clears the screen and the scrollback buffer
-            println!("\nMining for a valid hash...");
-            let (next_hash, nonce) =
-                self.find_next_hash_par(proof.hash.into(), treasury.difficulty.into(), threads);
+                        // Claim when asked to from the dashboard, as automatic claims do
+                        if let Some(dashboard) = dashboard.as_ref() {
+                            if dashboard.take_claim_request() {
+                                if let Some(claimed) = self
+                                    .auto_claim(claim_to.clone(), floor, Some(dashboard))
+                                    .await
+                                {
+                                    session.record_claim(
+                                        claimed,
+                                        estimate_fee(CU_LIMIT_CLAIM, self.priority_fee),
+                                    );
+                                    dashboard.update(|state| state.fees_spent = session.fees_spent);
+                                }
+                            }
+                        }
+                        let threads = dashboard.as_ref().map_or(args.threads, |d| d.threads());
+                        let (nonce_start, nonce_end) = match external_hasher {
+                            Some(_) => cursor.next_range(external_hasher::RANGE_SIZE),
+                            None => cursor.next_block(threads),
+                        };
+                        let block = HashParams {
+                            nonce_start,
+                            nonce_end,
+                            ..params
+                        };
+                        let searched;
+                        (solution, searched) = match (external_hasher.as_mut(), dashboard.as_ref())
+                        {
+                            (Some(hasher), _) => match hasher.find_hash(block, &stop) {
+                                Ok((solution, searched)) => {
+                                    // The hasher runs in its own process, so rest between blocks
+                                    counters.add(0, searched.saturating_sub(nonce_start));
+                                    throttle.rest();
+                                    (solution, searched)
+                                }
+                                Err(err) => {
+                                    log::error!("{}", err);
+                                    hasher_failed = true;
+                                    break;
+                                }
+                            },
+                            (None, Some(dashboard)) => {
+                                self.find_hash_with_dashboard(block, &counters, &stop, dashboard)
+                                    .await
+                            }
+                            (None, None) => {
+                                find_hash_par(block, threads, &counters, stop.clone(), None)
+                            }
+                        };
+                        if solution.is_some() {
+                            break;
+                        }
+                        cursor.advance(searched);
+                    }
+                    hashes = counters.total() - hashes_before;
+                    solution
+                }
+            };
+            let solve_secs = solve_started_at.elapsed().as_secs_f64();
+            session.hashes += hashes;
 
-            // Submit mine tx.
-            // Use busses randomly so on each epoch, transactions don't pile on the same busses
-            println!("\n\nSubmitting hash for validation...");
-            loop {
-                // Reset epoch, if needed
-                let treasury = get_treasury(self.cluster.clone()).await;
-                let clock = get_clock_account(self.cluster.clone()).await;
-                let threshold = treasury.last_reset_at.saturating_add(EPOCH_DURATION);
-                if clock.unix_timestamp.ge(&threshold) {
-                    // There are a lot of miners right now, so randomly select into submitting tx
-                    if rng.gen_range(0..RESET_ODDS).eq(&0) {
-                        println!("Sending epoch reset transaction...");
-                        let cu_limit_ix =
-                            ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_RESET);
-                        let cu_price_ix =
-                            ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
-                        let reset_ix = ore::instruction::reset(signer.pubkey());
-                        self.send_and_confirm(&[cu_limit_ix, cu_price_ix, reset_ix], true)
-                            .await
-                            .ok();
+            // Refetch the challenge and difficulty after a pause, they have likely changed
+            if hasher_failed {
+                break;
+            }
+            if reopened {
+                continue;
+            }
+            let Some((next_hash, nonce)) = solution else {
+                match session.stop_reason(&args) {
+                    Some(reason) => log::info!("{}, stopping.", reason),
+                    None => {
+                        log::info!("Interrupted, stopping.");
+                        if let Some(history) = history.as_ref() {
+                            history.record_attempt(
+                                &authority,
+                                &challenge,
+                                &AttemptRecord::new("session", "interrupted"),
+                            );
+                        }
                     }
                 }
+                break;
+            };
 
-                // Submit request.
-                let bus = self.find_bus_id(treasury.reward_rate).await;
-                let bus_rewards = (bus.rewards as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
-                println!("Sending on bus {} ({} ORE)", bus.id, bus_rewards);
-                let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_MINE);
-                let cu_price_ix =
-                    ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
-                let ix_mine = ore::instruction::mine(
-                    signer.pubkey(),
-                    BUS_ADDRESSES[bus.id as usize],
-                    next_hash.into(),
+            // A resumed hash was counted when it was found
+            if !resumed {
+                session.solutions += 1;
+                if let Some(metrics) = metrics.as_ref() {
+                    metrics.record_solution();
+                }
+                log::info!("Found hash {} (nonce {})", next_hash, nonce);
+                events::emit(Event::SolutionFound {
+                    challenge: params.challenge.to_string(),
+                    hash: next_hash.to_string(),
                     nonce,
-                );
-                match self
-                    .send_and_confirm(&[cu_limit_ix, cu_price_ix, ix_mine], false)
+                    hashes,
+                    solve_secs,
+                });
+            }
+
+            // Submit mine tx.
+            log::info!("Submitting hash for validation...");
+            if let Some(dashboard) = dashboard.as_ref() {
+                dashboard.update(|state| state.status = "Submitting");
+            }
+            let priority_fee = match (args.min_profit, args.ore_price.as_ref()) {
+                (Some(min_profit), Some(price)) => {
+                    self.profitable_priority_fee(
+                        min_profit,
+                        price,
+                        &mut last_price,
+                        &stop,
+                        dashboard.as_deref(),
+                    )
                     .await
+                }
+                _ => Some(self.priority_fee),
+            };
+            let submission = match priority_fee {
+                Some(priority_fee) => Some(
+                    self.submit_hash(&signer, next_hash, nonce, priority_fee, floor, Some(&stop))
+                        .await,
+                ),
+                None => None,
+            };
+            if let (Some(history), Some(submission)) = (history.as_ref(), submission.as_ref()) {
+                for attempt in submission.attempts.iter() {
+                    history.record_attempt(&authority, &challenge, attempt);
+                }
+            }
+            if let Some((submission, priority_fee)) = submission.as_ref().zip(priority_fee) {
+                session.transactions_sent += submission.transactions_sent;
+                session.fees_spent += submission.fees;
+                if let Some(metrics) = metrics.as_ref() {
+                    metrics.record_submission(
+                        submission.transactions_sent,
+                        &submission.failures,
+                        submission.signature.is_some(),
+                        priority_fee,
+                    );
+                }
+            }
+            let below_floor = submission.as_ref().map_or(false, |s| s.below_floor);
+            let Some((submission, signature)) =
+                submission.and_then(|s| s.signature.map(|signature| (s, signature)))
+            else {
+                let held = PendingSolution {
+                    authority: signer.pubkey(),
+                    challenge: params.challenge,
+                    hash: next_hash,
+                    nonce,
+                };
+
+                // Hold the hash until the balance is topped up, then submit it
+                if below_floor {
+                    if let Some(history) = history.as_ref() {
+                        history.record_attempt(
+                            &authority,
+                            &challenge,
+                            &AttemptRecord::new("solution", "held"),
+                        );
+                    }
+                    pending = Some(held);
+                    continue;
+                }
+                held.save();
+                if let Some(history) = history.as_ref() {
+                    let outcome = if priority_fee.is_some() {
+                        "interrupted"
+                    } else {
+                        "held"
+                    };
+                    history.record_attempt(
+                        &authority,
+                        &challenge,
+                        &AttemptRecord::new("solution", outcome),
+                    );
+                }
+                log::info!("Interrupted, saved unsubmitted hash for the next session.");
+                break;
+            };
+            session.transactions_landed += 1;
+            if let Some(dashboard) = dashboard.as_ref() {
+                dashboard.update(|state| {
+                    state.solutions = session.solutions;
+                    state.fees_spent = session.fees_spent;
+                    state.push_transaction(RecentTransaction {
+                        landed_at: chrono::Utc::now().timestamp(),
+                        signature: signature.to_string(),
+                        bus: submission.bus,
+                        attempts: submission.transactions_sent,
+                        landing_secs: submission.landing_secs,
+                    });
+                });
+            }
+
+            // Record the solution
+            if let Some(history) = history.as_ref() {
+                let landed_proof = get_proof(self.cluster.clone(), signer.pubkey()).await;
+                history.record(&SolutionRecord {
+                    timestamp: chrono::Utc::now().timestamp(),
+                    authority: authority.clone(),
+                    challenge: challenge.clone(),
+                    hash: next_hash.to_string(),
+                    nonce,
+                    hashes,
+                    solve_secs,
+                    bus: submission.bus,
+                    transactions_sent: submission.transactions_sent,
+                    fee_lamports: submission.fees,
+                    signature: signature.to_string(),
+                    landing_secs: submission.landing_secs,
+                    ore_earned: landed_proof
+                        .total_rewards
+                        .saturating_sub(proof.total_rewards),
+                });
+            }
+        }
+
+        progress.stop();
+        if let Some(dashboard) = dashboard.as_ref() {
+            dashboard.close();
+        }
+
+        // Keep a hash which was held or not yet resumed for the next session
+        if let Some(pending) = pending.take() {
+            pending.save();
+            log::info!(
+                "Saved unsubmitted hash {} for the next session.",
+                pending.hash
+            );
+        }
+
+        // Account for rewards and fees of the last transactions
+        let proof = get_proof(self.cluster.clone(), signer.pubkey()).await;
+        session.update_rewards(proof.total_rewards);
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        if let Ok(sol_balance) = client.get_balance(&signer.pubkey()).await {
+            session.update_sol_balance(sol_balance);
+        }
+        session.print_summary();
+        if let Some(summary_file) = args.summary_file.as_ref() {
+            session.write_json(summary_file);
+        }
+    }
+
+    /// Submits a mine transaction, resetting the epoch first if needed, and retries until it lands.
+    /// If `stop` is set, gives up after the current attempt without a signature.
+    pub async fn submit_hash(
+        &self,
+        signer: &Keypair,
+        next_hash: KeccakHash,
+        nonce: u64,
+        priority_fee: u64,
+        floor: u64,
+        stop: Option<&AtomicBool>,
+    ) -> Submission {
+        let started_at = Instant::now();
+        let mut submission = Submission {
+            signature: None,
+            fees: 0,
+            transactions_sent: 0,
+            bus: 0,
+            landing_secs: 0.0,
+            failures: vec![],
+            attempts: vec![],
+            below_floor: false,
+        };
+
+        // Use busses randomly so on each epoch, transactions don't pile on the same busses
+        loop {
+            if submission.transactions_sent > 0
+                && stop.map_or(false, |stop| stop.load(Ordering::Relaxed))
+            {
+                return submission;
+            }
+
+            // Reset epoch, if needed
+            let treasury = get_treasury(self.cluster.clone()).await;
+            let clock = get_clock_account(self.cluster.clone()).await;
+            let threshold = treasury.last_reset_at.saturating_add(EPOCH_DURATION);
+            if clock.unix_timestamp.ge(&threshold) {
+                // There are a lot of miners right now, so randomly select into submitting tx
+                if rand::thread_rng().gen_range(0..RESET_ODDS).eq(&0)
+                    && self
+                        .covers_floor(signer, floor, estimate_fee(CU_LIMIT_RESET, priority_fee))
+                        .await
                 {
-                    Ok(sig) => {
-                        println!("Success: {}", sig);
-                        break;
+                    log::info!("Sending epoch reset transaction...");
+                    let cu_limit_ix =
+                        ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_RESET);
+                    let cu_price_ix =
+                        ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
+                    let reset_ix = ore::instruction::reset(signer.pubkey());
+                    match self
+                        .send_and_confirm_with_signers(
+                            &[cu_limit_ix, cu_price_ix, reset_ix],
+                            &[signer],
+                            true,
+                        )
+                        .await
+                    {
+                        Ok(sig) => {
+                            let fee = estimate_fee(CU_LIMIT_RESET, priority_fee);
+                            submission.fees += fee;
+                            submission.attempts.push(AttemptRecord {
+                                signature: Some(sig.to_string()),
+                                fee_lamports: fee,
+                                ..AttemptRecord::new("reset", "landed")
+                            });
+                            events::emit(Event::ResetSent {
+                                signature: sig.to_string(),
+                            });
+                        }
+                        Err(err) => {
+                            submission.attempts.push(AttemptRecord {
+                                error: Some(err.to_string()),
+                                ..AttemptRecord::new("reset", "failed")
+                            });
+                        }
                     }
-                    Err(_err) => {
-                        // TODO
+                } else {
+                    events::emit(Event::EpochWaiting {
+                        seconds_overdue: clock.unix_timestamp.saturating_sub(threshold),
+                    });
+                }
+            }
+
+            // Submit request, unless the fee would take the balance below the floor
+            if !self
+                .covers_floor(signer, floor, estimate_fee(CU_LIMIT_MINE, priority_fee))
+                .await
+            {
+                submission.below_floor = true;
+                return submission;
+            }
+            let bus = self.find_bus_id(treasury.reward_rate).await;
+            let bus_rewards = (bus.rewards as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
+            log::info!("Sending on bus {} ({} ORE)", bus.id, bus_rewards);
+            let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_MINE);
+            let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
+            let ix_mine = ore::instruction::mine(
+                signer.pubkey(),
+                BUS_ADDRESSES[bus.id as usize],
+                next_hash.into(),
+                nonce,
+            );
+            submission.transactions_sent += 1;
+            submission.bus = bus.id;
+            events::emit(Event::TxSent {
+                attempt: submission.transactions_sent,
+                bus: bus.id,
+            });
+            match self
+                .send_and_confirm_with_signers(
+                    &[cu_limit_ix, cu_price_ix, ix_mine],
+                    &[signer],
+                    false,
+                )
+                .await
+            {
+                Ok(sig) => {
+                    log::info!("Success: {}", sig);
+                    let fee = estimate_fee(CU_LIMIT_MINE, priority_fee);
+                    submission.fees += fee;
+                    submission.landing_secs = started_at.elapsed().as_secs_f64();
+                    submission.signature = Some(sig);
+                    submission.attempts.push(AttemptRecord {
+                        signature: Some(sig.to_string()),
+                        bus: Some(bus.id),
+                        fee_lamports: fee,
+                        ..AttemptRecord::new("mine", "landed")
+                    });
+                    events::emit(Event::TxLanded {
+                        signature: sig.to_string(),
+                        bus: bus.id,
+                        attempts: submission.transactions_sent,
+                        fees_lamports: submission.fees,
+                        landing_secs: submission.landing_secs,
+                    });
+                    return submission;
+                }
+                Err(err) => {
+                    log::warn!("Mine transaction failed: {}", err);
+                    submission.failures.push(error_class(&err));
+                    submission.attempts.push(AttemptRecord {
+                        bus: Some(bus.id),
+                        error: Some(err.to_string()),
+                        ..AttemptRecord::new("mine", "failed")
+                    });
+                    events::emit(Event::TxFailed {
+                        attempt: submission.transactions_sent,
+                        bus: bus.id,
+                        error_class: error_class(&err),
+                        error: err.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns whether the signer can pay `fee` and keep at least `floor` lamports. Assumes it can
+    /// if there is no floor, or the balance can't be fetched.
+    async fn covers_floor(&self, signer: &Keypair, floor: u64, fee: u64) -> bool {
+        if floor == 0 {
+            return true;
+        }
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        match client.get_balance(&signer.pubkey()).await {
+            Ok(balance) if balance < floor.saturating_add(fee) => {
+                log::warn!(
+                    "SOL balance {} can't pay a {} SOL fee above the {} SOL minimum, holding hash",
+                    lamports_to_sol(balance),
+                    lamports_to_sol(fee),
+                    lamports_to_sol(floor)
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the priority fee to submit a hash with, so the reward is worth at least
+    /// `min_profit` SOL more than the fee. The configured fee is lowered if that is enough,
+    /// otherwise the hash is held until it is profitable. If the price can't be fetched, the last
+    /// price is used until it is stale, then the hash is held until a price is available. Returns
+    /// `None` if mining stopped first.
+    async fn profitable_priority_fee(
+        &self,
+        min_profit: f64,
+        source: &PriceSource,
+        last_price: &mut Option<(f64, Instant)>,
+        stop: &AtomicBool,
+        dashboard: Option<&Dashboard>,
+    ) -> Option<u64> {
+        let mut holding = false;
+        loop {
+            let price = match source.fetch().await {
+                Ok(price) => {
+                    *last_price = Some((price, Instant::now()));
+                    Some(price)
+                }
+                Err(err) => match *last_price {
+                    Some((price, fetched_at)) if fetched_at.elapsed() < MAX_PRICE_AGE => {
+                        log::warn!(
+                            "{}, using the price from {} ago",
+                            err,
+                            format_duration(fetched_at.elapsed())
+                        );
+                        Some(price)
+                    }
+                    _ => {
+                        log::warn!("{}, holding hash until a price is available", err);
+                        if let Some(dashboard) = dashboard {
+                            dashboard.update(|state| state.status = "Holding (no price)");
+                        }
+                        None
                     }
+                },
+            };
+            let Some(price) = price else {
+                if !wait_or_stop(PROFIT_CHECK_INTERVAL, stop).await {
+                    return None;
+                }
+                continue;
+            };
+            let treasury = get_treasury(self.cluster.clone()).await;
+            let reward = amount_u64_to_f64(treasury.reward_rate) * price;
+            let max_fee = ((reward - min_profit) * LAMPORTS_PER_SOL as f64).floor();
+            let fee = estimate_fee(CU_LIMIT_MINE, self.priority_fee);
+            let base_fee = estimate_fee(CU_LIMIT_MINE, 0);
+            if fee as f64 <= max_fee {
+                return Some(self.priority_fee);
+            }
+            if base_fee as f64 <= max_fee {
+                let priority_fee =
+                    (max_fee as u64 - base_fee).saturating_mul(1_000_000) / CU_LIMIT_MINE as u64;
+                log::info!(
+                    "Lowering the priority fee to {} microlamports to stay profitable",
+                    priority_fee
+                );
+                return Some(priority_fee);
+            }
+            if !holding {
+                holding = true;
+                log::info!(
+                    "Reward is worth {:.6} SOL, less than the {} SOL fee plus {} SOL minimum profit, holding hash",
+                    reward,
+                    lamports_to_sol(base_fee),
+                    min_profit
+                );
+                if let Some(dashboard) = dashboard {
+                    dashboard.update(|state| state.status = "Holding (unprofitable)");
                 }
             }
+            if !wait_or_stop(PROFIT_CHECK_INTERVAL, stop).await {
+                return None;
+            }
+        }
+    }
+
+    /// Alerts that the SOL balance is below `min_lamports`, the floor plus a mine fee, and waits
+    /// until it is topped up. Returns false if mining stopped first.
+    async fn wait_for_min_sol(
+        &self,
+        mut balance: u64,
+        min_lamports: u64,
+        stop: &AtomicBool,
+        dashboard: Option<&Dashboard>,
+    ) -> bool {
+        log::warn!(
+            "SOL balance {} is below the {} SOL minimum plus fees, pausing until {} is topped up",
+            lamports_to_sol(balance),
+            lamports_to_sol(min_lamports),
+            self.signer().pubkey()
+        );
+        events::emit(Event::LowBalance {
+            balance_lamports: balance,
+            min_lamports,
+        });
+        if let Some(dashboard) = dashboard {
+            dashboard.update(|state| state.status = "Low SOL balance");
+        }
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        while balance < min_lamports {
+            if !wait_or_stop(BALANCE_CHECK_INTERVAL, stop).await {
+                return false;
+            }
+            balance = client
+                .get_balance(&self.signer().pubkey())
+                .await
+                .unwrap_or(balance);
+        }
+        log::info!(
+            "SOL balance topped up to {}, resuming",
+            lamports_to_sol(balance)
+        );
+        if let Some(dashboard) = dashboard {
+            dashboard.update(|state| state.status = "Mining");
+        }
+        true
+    }
+
+    /// Claims all rewards, if the signer has enough SOL to pay for the claim and the next mine
+    /// transaction and keep `floor` lamports.
+    async fn auto_claim(
+        &self,
+        claim_to: Option<String>,
+        floor: u64,
+        dashboard: Option<&Dashboard>,
+    ) -> Option<Claimed> {
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        let required = estimate_fee(CU_LIMIT_CLAIM, self.priority_fee)
+            + estimate_fee(CU_LIMIT_MINE, self.priority_fee)
+            + floor;
+        match client.get_balance(&self.signer().pubkey()).await {
+            Ok(balance) if balance >= required => {}
+            Ok(balance) => {
+                log::warn!(
+                    "Not enough SOL to claim automatically: {} SOL, need {} SOL",
+                    lamports_to_sol(balance),
+                    lamports_to_sol(required)
+                );
+                return None;
+            }
+            Err(err) => {
+                log::warn!("Failed to fetch SOL balance: {}", err);
+                return None;
+            }
+        }
+        if let Some(dashboard) = dashboard {
+            dashboard.update(|state| state.status = "Claiming");
+        }
+        let claimed = self.claim(self.cluster.clone(), claim_to, None).await;
+        if let Some(dashboard) = dashboard {
+            dashboard.update(|state| state.status = "Mining");
+        }
+        claimed
+    }
+
+    /// Waits for the mining schedule to open, showing it on the dashboard.
+    async fn wait_for_schedule(
+        &self,
+        schedule: &Schedule,
+        stop: &AtomicBool,
+        dashboard: Option<&Dashboard>,
+    ) -> ScheduleWait {
+        if schedule.is_open() {
+            return ScheduleWait::Open;
+        }
+        if let Some(dashboard) = dashboard {
+            dashboard.update(|state| state.status = "Outside schedule");
+        }
+        let open = schedule.wait(stop).await;
+        if let Some(dashboard) = dashboard {
+            dashboard.update(|state| state.status = "Mining");
+        }
+        match open {
+            true => ScheduleWait::Reopened,
+            false => ScheduleWait::Stopped,
         }
     }
 
     async fn find_bus_id(&self, reward_rate: u64) -> Bus {
-        let mut rng = rand::thread_rng();
         loop {
-            let bus_id = rng.gen_range(0..BUS_COUNT);
+            let bus_id = rand::thread_rng().gen_range(0..BUS_COUNT);
             if let Ok(bus) = self.get_bus(bus_id).await {
                 if bus.rewards.gt(&reward_rate.saturating_mul(4)) {
                     return bus;
@@ -127,78 +1021,13 @@ impl Miner {
             if next_hash.le(&difficulty) {
                 break;
             } else {
-                println!("Invalid hash: {} Nonce: {:?}", next_hash.to_string(), nonce);
+                log::trace!("Invalid hash: {} Nonce: {:?}", next_hash, nonce);
             }
             nonce += 1;
         }
         (next_hash, nonce)
     }
 
-    fn find_next_hash_par(
-        &self,
-        hash: KeccakHash,
-        difficulty: KeccakHash,
-        threads: u64,
-    ) -> (KeccakHash, u64) {
-        let found_solution = Arc::new(AtomicBool::new(false));
-        let solution = Arc::new(Mutex::<(KeccakHash, u64)>::new((
-            KeccakHash::new_from_array([0; 32]),
-            0,
-        )));
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-        let thread_handles: Vec<_> = (0..threads)
-            .map(|i| {
-                std::thread::spawn({
-                    let found_solution = found_solution.clone();
-                    let solution = solution.clone();
-                    let mut stdout = stdout();
-                    move || {
-                        let n = u64::MAX.saturating_div(threads).saturating_mul(i);
-                        let mut next_hash: KeccakHash;
-                        let mut nonce: u64 = n;
-                        loop {
-                            next_hash = hashv(&[
-                                hash.to_bytes().as_slice(),
-                                pubkey.to_bytes().as_slice(),
-                                nonce.to_le_bytes().as_slice(),
-                            ]);
-                            if nonce % 10_000 == 0 {
-                                if found_solution.load(std::sync::atomic::Ordering::Relaxed) {
-                                    return;
-                                }
-                                if n == 0 {
-                                    stdout
-                                        .write_all(
-                                            format!("\r{}", next_hash.to_string()).as_bytes(),
-                                        )
-                                        .ok();
-                                }
-                            }
-                            if next_hash.le(&difficulty) {
-                                stdout
-                                    .write_all(format!("\r{}", next_hash.to_string()).as_bytes())
-                                    .ok();
-                                found_solution.store(true, std::sync::atomic::Ordering::Relaxed);
-                                let mut w_solution = solution.lock().expect("failed to lock mutex");
-                                *w_solution = (next_hash, nonce);
-                                return;
-                            }
-                            nonce += 1;
-                        }
-                    }
-                })
-            })
-            .collect();
-
-        for thread_handle in thread_handles {
-            thread_handle.join().unwrap();
-        }
-
-        let r_solution = solution.lock().expect("Failed to get lock");
-        *r_solution
-    }
-
     pub async fn get_ore_display_balance(&self) -> String {
         let client =
             RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
@@ -219,3 +1048,191 @@ impl Miner {
         }
     }
 }
+
+/// Emits a progress event whenever the hash counters have advanced.
+async fn emit_progress(counters: Arc<HashCounters>) {
+    let mut last_hashes = counters.total();
+    let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let hashes = counters.total();
+        if hashes.eq(&last_hashes) {
+            continue;
+        }
+        events::emit(Event::Progress {
+            hashes,
+            hashrate: hashes.saturating_sub(last_hashes) as f64 / PROGRESS_INTERVAL.as_secs_f64(),
+        });
+        last_hashes = hashes;
+    }
+}
+
+/// Sleeps for `duration`, waking early if `stop` is set. Returns false if it was.
+async fn wait_or_stop(duration: Duration, stop: &AtomicBool) -> bool {
+    let started_at = Instant::now();
+    while started_at.elapsed() < duration {
+        if stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    true
+}
+
+/// Sets `stop` on the first Ctrl-C or SIGTERM, and exits the process on the second.
+async fn handle_signals(stop: Arc<AtomicBool>) {
+    wait_for_signal().await;
+    log::warn!("Shutting down, press Ctrl-C again to force exit...");
+    stop.store(true, Ordering::Relaxed);
+    wait_for_signal().await;
+    log::warn!("Forcing exit.");
+    std::process::exit(130);
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = sigterm.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    tokio::signal::ctrl_c().await.ok();
+}
+
+/// Hashes computed by each hashing thread, shared with whoever is reporting progress.
+pub struct HashCounters {
+    threads: Vec<AtomicU64>,
+}
+
+impl HashCounters {
+    pub fn new(threads: u64) -> Self {
+        Self {
+            threads: (0..threads).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn add(&self, thread: u64, hashes: u64) {
+        self.threads[thread as usize].fetch_add(hashes, Ordering::Relaxed);
+    }
+
+    /// Returns the number of threads the counters can track.
+    pub fn threads(&self) -> u64 {
+        self.threads.len() as u64
+    }
+
+    pub fn total(&self) -> u64 {
+        self.threads
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    pub fn per_thread(&self) -> Vec<u64> {
+        self.threads
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+}
+
+/// The inputs to a hash search over a range of nonces.
+#[derive(Clone, Copy, Debug)]
+pub struct HashParams {
+    pub challenge: KeccakHash,
+    pub authority: Pubkey,
+    pub difficulty: KeccakHash,
+    pub nonce_start: u64,
+    pub nonce_end: u64,
+}
+
+/// Searches `nonce_start..nonce_end` for a nonce whose hash satisfies the difficulty, handing out
+/// the range to threads a chunk at a time. Returns the solution, if one was found, and the nonce
+/// before which every nonce has been searched, so a stopped search can pick up where it left off.
+/// If a share difficulty and channel are given, every nonce whose hash satisfies the share
+/// difficulty is also sent to the channel.
+pub fn find_hash_par(
+    params: HashParams,
+    threads: u64,
+    counters: &Arc<HashCounters>,
+    stop: Arc<AtomicBool>,
+    shares: Option<(KeccakHash, UnboundedSender<u64>)>,
+) -> (Option<(KeccakHash, u64)>, u64) {
+    let HashParams {
+        challenge: hash,
+        authority,
+        difficulty,
+        nonce_start,
+        nonce_end,
+    } = params;
+    let found_solution = Arc::new(AtomicBool::new(false));
+    let solution = Arc::new(Mutex::<Option<(KeccakHash, u64)>>::new(None));
+
+    // Chunks are handed out in order and only abandoned once finished, so every nonce before
+    // the next chunk has been searched
+    let next_chunk = Arc::new(AtomicU64::new(nonce_start));
+    let thread_handles: Vec<_> = (0..threads)
+        .map(|i| {
+            std::thread::spawn({
+                let found_solution = found_solution.clone();
+                let solution = solution.clone();
+                let next_chunk = next_chunk.clone();
+                let stop = stop.clone();
+                let shares = shares.clone();
+                let counters = counters.clone();
+                move || {
+                    cpu::setup_hash_thread(i);
+                    let mut throttle = cpu::Throttle::new();
+                    let mut next_hash: KeccakHash;
+                    while !found_solution.load(Ordering::Relaxed) && !stop.load(Ordering::Relaxed) {
+                        let start = next_chunk
+                            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                                Some(n.saturating_add(NONCES_PER_CHUNK))
+                            })
+                            .unwrap();
+                        if start >= nonce_end {
+                            return;
+                        }
+                        let end = start.saturating_add(NONCES_PER_CHUNK).min(nonce_end);
+                        for nonce in start..end {
+                            next_hash = hashv(&[
+                                hash.to_bytes().as_slice(),
+                                authority.to_bytes().as_slice(),
+                                nonce.to_le_bytes().as_slice(),
+                            ]);
+                            if let Some((share_difficulty, shares)) = shares.as_ref() {
+                                if next_hash.le(share_difficulty) {
+                                    shares.send(nonce).ok();
+                                }
+                            }
+                            if next_hash.le(&difficulty) {
+                                counters.add(i, nonce + 1 - start);
+                                found_solution.store(true, Ordering::Relaxed);
+                                let mut w_solution = solution.lock().expect("failed to lock mutex");
+                                *w_solution = Some((next_hash, nonce));
+                                return;
+                            }
+                        }
+                        counters.add(i, end - start);
+                        throttle.rest();
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for thread_handle in thread_handles {
+        thread_handle.join().unwrap();
+    }
+
+    let r_solution = solution.lock().expect("Failed to get lock");
+    let searched = next_chunk
+        .load(Ordering::Relaxed)
+        .clamp(nonce_start, nonce_end);
+    (*r_solution, searched)
+}
@@ -0,0 +1,213 @@
+use std::{fs, path::PathBuf, str::FromStr};
+
+use rand::Rng;
+use serde_json::json;
+use solana_sdk::{keccak::Hash as KeccakHash, pubkey::Pubkey};
+
+use crate::utils::state_dir;
+
+// The number of nonces each thread searches between checkpoints
+const NONCES_PER_THREAD: u64 = 1 << 26;
+
+/// The part of the nonce space a miner searches for a challenge, and how far it has got.
+///
+/// Without a worker id, each challenge starts at a random nonce, so machines and restarts mining
+/// the same authority don't repeat each other's work. With `--worker-id` and `--worker-count`,
+/// the nonce space is split evenly and each worker searches its own share. The position is
+/// checkpointed after every block, so a restart on the same challenge continues where it left off.
+pub struct NonceCursor {
+    dir: PathBuf,
+    authority: Pubkey,
+    challenge: KeccakHash,
+    start: u64,
+    next: u64,
+    end: u64,
+}
+
+impl NonceCursor {
+    pub fn new(authority: Pubkey, challenge: KeccakHash, worker: Option<(u64, u64)>) -> Self {
+        Self::with_dir(state_dir(), authority, challenge, worker)
+    }
+
+    /// Creates a cursor which checkpoints to `dir`.
+    pub fn with_dir(
+        dir: PathBuf,
+        authority: Pubkey,
+        challenge: KeccakHash,
+        worker: Option<(u64, u64)>,
+    ) -> Self {
+        let (start, end) = match worker {
+            Some((id, count)) => {
+                let share = u64::MAX / count;
+                let end = if id + 1 == count {
+                    u64::MAX
+                } else {
+                    share * (id + 1)
+                };
+                (share * id, end)
+            }
+            None => (rand::thread_rng().gen_range(0..u64::MAX / 2), u64::MAX),
+        };
+        let mut cursor = Self {
+            dir,
+            authority,
+            challenge,
+            start,
+            next: start,
+            end,
+        };
+        if let Some((start, next, end)) = cursor.load() {
+            if worker.is_none() || (start, end).eq(&(cursor.start, cursor.end)) {
+                log::info!("Resuming challenge {} at nonce {}", challenge, next);
+                (cursor.start, cursor.next, cursor.end) = (start, next, end);
+            }
+        }
+        cursor
+    }
+
+    pub fn challenge(&self) -> KeccakHash {
+        self.challenge
+    }
+
+    /// Returns the nonces left to search.
+    pub fn remaining(&self) -> (u64, u64) {
+        (self.next, self.end)
+    }
+
+    /// Returns the next block of nonces for `threads` to search.
+    pub fn next_block(&mut self, threads: u64) -> (u64, u64) {
+        self.next_range(NONCES_PER_THREAD.saturating_mul(threads.max(1)))
+    }
+
+    /// Returns up to `size` nonces to search next. Once the range is exhausted, it starts over.
+    pub fn next_range(&mut self, size: u64) -> (u64, u64) {
+        if self.next >= self.end {
+            self.next = self.start;
+        }
+        (self.next, self.next.saturating_add(size).min(self.end))
+    }
+
+    /// Marks every nonce before `nonce` as searched and checkpoints the position.
+    pub fn advance(&mut self, nonce: u64) {
+        self.next = nonce;
+        let data = json!({
+            "challenge": self.challenge.to_string(),
+            "start": self.start,
+            "next": self.next,
+            "end": self.end,
+        });
+        if let Err(err) = fs::write(self.path(), data.to_string()) {
+            log::warn!("Failed to write {}: {}", self.path().display(), err);
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(format!("checkpoint-{}.json", self.authority))
+    }
+
+    /// Loads the checkpointed range and position, if it is for this challenge.
+    fn load(&self) -> Option<(u64, u64, u64)> {
+        let data = fs::read_to_string(self.path()).ok()?;
+        let data: serde_json::Value = serde_json::from_str(&data).ok()?;
+        let challenge = KeccakHash::from_str(data["challenge"].as_str()?).ok()?;
+        if challenge.ne(&self.challenge) {
+            return None;
+        }
+        Some((
+            data["start"].as_u64()?,
+            data["next"].as_u64()?,
+            data["end"].as_u64()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(byte: u8) -> KeccakHash {
+        KeccakHash::new_from_array([byte; 32])
+    }
+
+    /// A checkpoint directory of its own, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("ore-nonces-{}", Pubkey::new_unique()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn cursor(
+            &self,
+            authority: Pubkey,
+            challenge: KeccakHash,
+            worker: Option<(u64, u64)>,
+        ) -> NonceCursor {
+            NonceCursor::with_dir(self.0.clone(), authority, challenge, worker)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    #[test]
+    fn workers_partition_the_nonce_space() {
+        let dir = TempDir::new();
+        let count = 3;
+        let ranges: Vec<(u64, u64)> = (0..count)
+            .map(|id| {
+                dir.cursor(Pubkey::new_unique(), challenge(1), Some((id, count)))
+                    .remaining()
+            })
+            .collect();
+        assert_eq!(ranges[0].0, 0);
+        assert_eq!(ranges[count as usize - 1].1, u64::MAX);
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].1, pair[1].0);
+        }
+    }
+
+    #[test]
+    fn next_range_wraps_within_the_partition() {
+        let dir = TempDir::new();
+        let mut cursor = dir.cursor(Pubkey::new_unique(), challenge(1), Some((1, 2)));
+        let (start, end) = cursor.remaining();
+        assert_eq!(cursor.next_range(10), (start, start + 10));
+        cursor.next = end - 4;
+        assert_eq!(cursor.next_range(10), (end - 4, end));
+        cursor.next = end;
+        assert_eq!(cursor.next_range(10), (start, start + 10));
+    }
+
+    #[test]
+    fn checkpoint_resumes_the_same_challenge_and_partition() {
+        let dir = TempDir::new();
+        let authority = Pubkey::new_unique();
+        let mut cursor = dir.cursor(authority, challenge(1), Some((0, 2)));
+        let (start, end) = cursor.remaining();
+        cursor.advance(start + 1000);
+        assert_eq!(
+            dir.cursor(authority, challenge(1), Some((0, 2)))
+                .remaining(),
+            (start + 1000, end)
+        );
+        assert_eq!(
+            dir.cursor(authority, challenge(1), None).remaining(),
+            (start + 1000, end)
+        );
+
+        // A new challenge or a different partition starts afresh
+        assert_eq!(
+            dir.cursor(authority, challenge(2), Some((0, 2)))
+                .remaining(),
+            (start, end)
+        );
+        let other = dir.cursor(authority, challenge(1), Some((1, 2)));
+        assert_ne!(other.remaining(), (start + 1000, end));
+    }
+}
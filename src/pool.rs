@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+// The protocol between `ore pool serve` and `ore pool work` is newline-delimited JSON over TCP.
+// Each line is one message, tagged by its `type` field. Hashes and pubkeys are base58 strings.
+//
+// On connect, the worker sends `hello` and the server replies with `work`. The server sends fresh
+// `work` whenever the challenge advances, and the worker asks for `next_range` when it exhausts
+// the range it was given. Solutions are sent back with `submit` and answered with `accepted` or
+// `rejected`. Hashes that only satisfy the easier `share_difficulty` are sent back with `share` and
+// credited to the payout address the worker gave in `hello`; only rejected shares are answered.
+
+/// Messages sent from the pool server to its workers.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Work {
+        challenge: String,
+        authority: String,
+        difficulty: String,
+        share_difficulty: String,
+        nonce_start: u64,
+        nonce_end: u64,
+    },
+    Accepted {
+        nonce: u64,
+    },
+    Rejected {
+        nonce: u64,
+        reason: String,
+    },
+}
+
+/// Messages sent from a worker to the pool server.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    Hello {
+        worker: String,
+        #[serde(default)]
+        payout: Option<String>,
+    },
+    NextRange,
+    Share {
+        challenge: String,
+        nonce: u64,
+    },
+    Submit {
+        challenge: String,
+        nonce: u64,
+    },
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    message: &T,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
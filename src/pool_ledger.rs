@@ -0,0 +1,361 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, signature::Signer};
+
+use crate::{
+    cu_limits::{CU_LIMIT_ATA, CU_LIMIT_CLAIM, CU_LIMIT_TRANSFER},
+    utils::amount_u64_to_f64,
+    Miner, PayoutMethod,
+};
+
+// Number of worker payouts packed into one transaction
+const PAYOUTS_PER_TX: usize = 4;
+
+/// Share accounting for a pool, persisted as JSON after credits and payouts. Shares are only
+/// persisted by `flush`, so workers are not held up writing the ledger for each one.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ledger {
+    #[serde(skip)]
+    path: PathBuf,
+
+    #[serde(skip)]
+    dirty: bool,
+
+    /// Payout addresses of the most recent shares, oldest first, for PPLNS.
+    pub recent_shares: VecDeque<String>,
+
+    /// Shares per payout address since the last landed mine transaction, for proportional splits.
+    pub round_shares: HashMap<String, u64>,
+
+    /// Rewards credited but not yet paid, per payout address, in nanoORE.
+    pub balances: HashMap<String, u64>,
+
+    /// Lifetime rewards paid, per payout address, in nanoORE.
+    pub total_paid: HashMap<String, u64>,
+
+    /// Rewards claimed into the pool token account but not yet transferred to workers.
+    pub unpaid_claimed: u64,
+
+    /// The proof's lifetime rewards at the last credit, used to detect new rewards.
+    pub last_total_rewards: Option<u64>,
+
+    /// Rewards not yet credited to anyone, from rounds without shares and rounding remainders.
+    /// These are added to the next reward.
+    #[serde(default)]
+    pub undistributed: u64,
+}
+
+impl Ledger {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut ledger: Ledger = match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?,
+            Err(_) => Ledger::default(),
+        };
+        ledger.path = path.to_path_buf();
+        Ok(ledger)
+    }
+
+    /// Writes the ledger to a temporary file and renames it over the ledger, so a crash never
+    /// leaves a partial ledger behind.
+    pub fn save(&mut self) {
+        let data = serde_json::to_string_pretty(self).unwrap();
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        if let Err(err) = fs::write(&tmp, data).and_then(|_| fs::rename(&tmp, &self.path)) {
+            log::warn!("Failed to write {}: {}", self.path.display(), err);
+            return;
+        }
+        self.dirty = false;
+    }
+
+    /// Saves shares recorded since the last save.
+    pub fn flush(&mut self) {
+        if self.dirty {
+            self.save();
+        }
+    }
+
+    pub fn record_share(&mut self, payout: &str, window: usize) {
+        self.recent_shares.push_back(payout.to_string());
+        while self.recent_shares.len() > window {
+            self.recent_shares.pop_front();
+        }
+        *self.round_shares.entry(payout.to_string()).or_default() += 1;
+        self.dirty = true;
+    }
+
+    /// Credits new proof rewards to workers according to the payout method. The first call only
+    /// records the proof's current total, so rewards mined before the pool started are not split.
+    pub fn credit(&mut self, total_rewards: u64, method: PayoutMethod) {
+        let Some(last_total_rewards) = self.last_total_rewards else {
+            self.last_total_rewards = Some(total_rewards);
+            self.save();
+            return;
+        };
+        let new_reward = total_rewards.saturating_sub(last_total_rewards);
+        if new_reward == 0 {
+            return;
+        }
+        let reward = new_reward.saturating_add(self.undistributed);
+        let mut shares = HashMap::<String, u64>::new();
+        match method {
+            PayoutMethod::Pplns => {
+                for payout in self.recent_shares.iter() {
+                    *shares.entry(payout.clone()).or_default() += 1;
+                }
+            }
+            PayoutMethod::Proportional => {
+                shares = std::mem::take(&mut self.round_shares);
+            }
+        }
+        self.round_shares.clear();
+        let total_shares: u64 = shares.values().sum();
+        let mut credited = 0;
+        if total_shares > 0 {
+            log::info!(
+                "Crediting {} ORE across {} shares",
+                amount_u64_to_f64(reward),
+                total_shares
+            );
+            for (payout, count) in shares {
+                let amount = (reward as u128 * count as u128 / total_shares as u128) as u64;
+                *self.balances.entry(payout).or_default() += amount;
+                credited += amount;
+            }
+        } else {
+            log::info!(
+                "No shares to credit, carrying {} ORE forward",
+                amount_u64_to_f64(reward)
+            );
+        }
+        self.undistributed = reward - credited;
+        self.last_total_rewards = Some(total_rewards);
+        self.save();
+    }
+
+    /// Returns every payout address owed at least `threshold` nanoORE.
+    pub fn payable(&self, threshold: u64) -> Vec<(String, u64)> {
+        let mut payable: Vec<(String, u64)> = self
+            .balances
+            .iter()
+            .filter(|(payout, amount)| {
+                amount.ge(&&threshold) && amount.gt(&&0) && Pubkey::from_str(payout).is_ok()
+            })
+            .map(|(payout, amount)| (payout.clone(), *amount))
+            .collect();
+        payable.sort();
+        payable
+    }
+
+    pub fn mark_paid(&mut self, payout: &str, amount: u64) {
+        if let Some(balance) = self.balances.get_mut(payout) {
+            *balance = balance.saturating_sub(amount);
+        }
+        *self.total_paid.entry(payout.to_string()).or_default() += amount;
+        self.unpaid_claimed = self.unpaid_claimed.saturating_sub(amount);
+    }
+}
+
+impl Miner {
+    /// Claims owed rewards into the pool's token account and transfers them to each worker.
+    pub async fn pool_payout(&self, ledger: &Mutex<Ledger>, threshold: u64) {
+        let signer = self.signer();
+        let (payable, unpaid_claimed) = {
+            let ledger = ledger.lock().unwrap();
+            (ledger.payable(threshold), ledger.unpaid_claimed)
+        };
+        let total: u64 = payable.iter().map(|(_, amount)| amount).sum();
+        if total == 0 {
+            return;
+        }
+        log::info!(
+            "Paying {} ORE to {} workers",
+            amount_u64_to_f64(total),
+            payable.len()
+        );
+
+        // Claim rewards which are not already sitting in the pool token account
+        let pool_tokens = self.initialize_ata().await;
+        let claim_amount = total.saturating_sub(unpaid_claimed);
+        if claim_amount > 0 {
+            let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_CLAIM);
+            let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
+            let ix = ore::instruction::claim(signer.pubkey(), pool_tokens, claim_amount);
+            if let Err(err) = self
+                .send_and_confirm(&[cu_limit_ix, cu_price_ix, ix], false)
+                .await
+            {
+                log::error!("Error claiming pool rewards: {:?}", err);
+                return;
+            }
+            let mut ledger = ledger.lock().unwrap();
+            ledger.unpaid_claimed = ledger.unpaid_claimed.saturating_add(claim_amount);
+            ledger.save();
+        }
+
+        // Transfer to workers
+        for batch in payable.chunks(PAYOUTS_PER_TX) {
+            let cu_limit = CU_LIMIT_ATA
+                .saturating_add(CU_LIMIT_TRANSFER)
+                .saturating_mul(batch.len() as u32);
+            let mut ixs = vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+                ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee),
+            ];
+            for (payout, amount) in batch {
+                let owner = Pubkey::from_str(payout).unwrap();
+                let worker_tokens = spl_associated_token_account::get_associated_token_address(
+                    &owner,
+                    &ore::MINT_ADDRESS,
+                );
+                ixs.push(
+                    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                        &signer.pubkey(),
+                        &owner,
+                        &ore::MINT_ADDRESS,
+                        &spl_token::id(),
+                    ),
+                );
+                ixs.push(
+                    spl_token::instruction::transfer(
+                        &spl_token::id(),
+                        &pool_tokens,
+                        &worker_tokens,
+                        &signer.pubkey(),
+                        &[],
+                        *amount,
+                    )
+                    .unwrap(),
+                );
+            }
+            match self.send_and_confirm(&ixs, false).await {
+                Ok(sig) => {
+                    let mut ledger = ledger.lock().unwrap();
+                    for (payout, amount) in batch {
+                        log::info!("Paid {} ORE to {}", amount_u64_to_f64(*amount), payout);
+                        ledger.mark_paid(payout, *amount);
+                    }
+                    ledger.save();
+                    log::info!("Paid {} workers in {}", batch.len(), sig);
+                }
+                Err(err) => {
+                    log::error!("Error paying workers: {:?}", err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> Ledger {
+        Ledger {
+            path: std::env::temp_dir().join(format!("ore-ledger-{}.json", Pubkey::new_unique())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pplns_credits_recent_window() {
+        let (a, b) = (
+            Pubkey::new_unique().to_string(),
+            Pubkey::new_unique().to_string(),
+        );
+        let mut ledger = ledger();
+        ledger.credit(1_000, PayoutMethod::Pplns);
+        ledger.record_share(&a, 3);
+        ledger.record_share(&a, 3);
+        ledger.record_share(&b, 3);
+        ledger.record_share(&b, 3);
+        ledger.credit(1_900, PayoutMethod::Pplns);
+        assert_eq!(ledger.balances[&a], 300);
+        assert_eq!(ledger.balances[&b], 600);
+        assert_eq!(ledger.undistributed, 0);
+
+        // The window carries over rounds
+        ledger.credit(2_200, PayoutMethod::Pplns);
+        assert_eq!(ledger.balances[&a], 400);
+        assert_eq!(ledger.balances[&b], 800);
+        let _ = fs::remove_file(&ledger.path);
+    }
+
+    #[test]
+    fn proportional_credits_round_and_carries_remainder() {
+        let (a, b) = (
+            Pubkey::new_unique().to_string(),
+            Pubkey::new_unique().to_string(),
+        );
+        let mut ledger = ledger();
+        ledger.credit(0, PayoutMethod::Proportional);
+
+        // A reward without shares is carried into the next round
+        ledger.credit(100, PayoutMethod::Proportional);
+        assert!(ledger.balances.is_empty());
+        assert_eq!(ledger.undistributed, 100);
+
+        ledger.record_share(&a, 10);
+        ledger.record_share(&b, 10);
+        ledger.record_share(&b, 10);
+        ledger.credit(201, PayoutMethod::Proportional);
+        assert_eq!(ledger.balances[&a], 67);
+        assert_eq!(ledger.balances[&b], 134);
+        assert_eq!(ledger.undistributed, 0);
+        assert!(ledger.round_shares.is_empty());
+
+        // Rounding dust is carried too
+        ledger.record_share(&a, 10);
+        ledger.record_share(&b, 10);
+        ledger.record_share(&b, 10);
+        ledger.credit(211, PayoutMethod::Proportional);
+        assert_eq!(ledger.balances[&a], 70);
+        assert_eq!(ledger.balances[&b], 140);
+        assert_eq!(ledger.undistributed, 1);
+        let _ = fs::remove_file(&ledger.path);
+    }
+
+    #[test]
+    fn save_and_load_round_trip_and_reject_corrupt_ledgers() {
+        let a = Pubkey::new_unique().to_string();
+        let mut ledger = ledger();
+        ledger.record_share(&a, 10);
+        assert!(!ledger.path.exists());
+        ledger.flush();
+        let loaded = Ledger::load(&ledger.path).unwrap();
+        assert_eq!(loaded.round_shares[&a], 1);
+
+        fs::write(&ledger.path, "{\"recent_shares\": [").unwrap();
+        assert!(Ledger::load(&ledger.path).is_err());
+        let _ = fs::remove_file(&ledger.path);
+    }
+
+    #[test]
+    fn payable_filters_by_threshold_and_address() {
+        let (a, b) = (
+            Pubkey::new_unique().to_string(),
+            Pubkey::new_unique().to_string(),
+        );
+        let mut ledger = ledger();
+        ledger.balances.insert(a.clone(), 500);
+        ledger.balances.insert(b.clone(), 50);
+        ledger.balances.insert("not-an-address".to_string(), 1_000);
+        assert_eq!(ledger.payable(100), vec![(a.clone(), 500)]);
+        let mut all = vec![(a.clone(), 500), (b.clone(), 50)];
+        all.sort();
+        assert_eq!(ledger.payable(0), all);
+
+        ledger.mark_paid(&a, 500);
+        assert_eq!(ledger.payable(0), vec![(b, 50)]);
+        assert_eq!(ledger.total_paid[&a], 500);
+    }
+}
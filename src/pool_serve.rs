@@ -0,0 +1,389 @@
+use std::{
+    collections::HashSet,
+    io,
+    net::SocketAddr,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::{
+    future::{Fuse, FusedFuture},
+    FutureExt,
+};
+use ore::{
+    state::{Proof, Treasury},
+    utils::AccountDeserialize,
+    TREASURY_ADDRESS,
+};
+use rand::Rng;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{
+    keccak::{hashv, Hash as KeccakHash},
+    pubkey::Pubkey,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signer};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::{lookup_host, TcpListener, TcpStream},
+    sync::{mpsc, watch},
+};
+
+use crate::{
+    pool::{write_message, ServerMessage, WorkerMessage},
+    pool_ledger::Ledger,
+    utils::proof_pubkey,
+    Miner, PoolServeArgs,
+};
+
+// How often to poll the proof and treasury for a new challenge or difficulty
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+struct PoolState {
+    authority: Pubkey,
+    challenge: KeccakHash,
+    difficulty: KeccakHash,
+    share_difficulty: KeccakHash,
+    next_nonce: u64,
+    range_size: u64,
+    submitting: bool,
+    seen_shares: HashSet<u64>,
+    pplns_window: usize,
+}
+
+impl PoolState {
+    /// Hands out the next disjoint nonce range for the current challenge.
+    fn next_work(&mut self) -> ServerMessage {
+        let nonce_start = self.next_nonce;
+        let nonce_end = nonce_start.saturating_add(self.range_size);
+        self.next_nonce = if nonce_end.eq(&u64::MAX) {
+            0
+        } else {
+            nonce_end
+        };
+        ServerMessage::Work {
+            challenge: self.challenge.to_string(),
+            authority: self.authority.to_string(),
+            difficulty: self.difficulty.to_string(),
+            share_difficulty: self.share_difficulty.max(self.difficulty).to_string(),
+            nonce_start,
+            nonce_end,
+        }
+    }
+
+    fn hash(&self, challenge: &str, nonce: u64) -> Result<KeccakHash, String> {
+        match KeccakHash::from_str(challenge) {
+            Ok(challenge) if challenge.eq(&self.challenge) => {}
+            _ => return Err("Stale challenge".to_string()),
+        }
+        Ok(hashv(&[
+            self.challenge.to_bytes().as_slice(),
+            self.authority.to_bytes().as_slice(),
+            nonce.to_le_bytes().as_slice(),
+        ]))
+    }
+
+    /// Checks a submitted share against the current challenge and share difficulty.
+    fn verify_share(&mut self, challenge: &str, nonce: u64) -> Result<(), String> {
+        let hash = self.hash(challenge, nonce)?;
+        if hash.gt(&self.share_difficulty.max(self.difficulty)) {
+            return Err("Hash does not satisfy share difficulty".to_string());
+        }
+        if !self.seen_shares.insert(nonce) {
+            return Err("Duplicate share".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks a submitted nonce against the current challenge and difficulty.
+    fn verify(&self, challenge: &str, nonce: u64) -> Result<KeccakHash, String> {
+        let hash = self.hash(challenge, nonce)?;
+        if hash.gt(&self.difficulty) {
+            return Err("Hash does not satisfy difficulty".to_string());
+        }
+        if self.submitting {
+            return Err("Challenge already solved".to_string());
+        }
+        Ok(hash)
+    }
+}
+
+impl Miner {
+    pub async fn pool_serve(&self, args: PoolServeArgs) {
+        // Workers are not authenticated, so only listen publicly when asked to
+        let addrs: Vec<SocketAddr> = match lookup_host(&args.listen).await {
+            Ok(addrs) => addrs.collect(),
+            Err(err) => {
+                log::error!("Invalid listen address {}: {}", args.listen, err);
+                return;
+            }
+        };
+        if !args.public && addrs.iter().any(|addr| !addr.ip().is_loopback()) {
+            log::error!(
+                "Refusing to listen on {} without --public, workers are not authenticated",
+                args.listen
+            );
+            return;
+        }
+
+        // Register, if needed.
+        let signer = self.signer();
+        self.register().await;
+
+        // Load current challenge
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        let (proof, treasury) = loop {
+            match fetch_challenge(&client, signer.pubkey()).await {
+                Ok(accounts) => break accounts,
+                Err(err) => {
+                    log::warn!("{}, retrying...", err);
+                    tokio::time::sleep(REFRESH_INTERVAL).await;
+                }
+            }
+        };
+        let state = Arc::new(Mutex::new(PoolState {
+            authority: signer.pubkey(),
+            challenge: proof.hash.into(),
+            difficulty: treasury.difficulty.into(),
+            share_difficulty: share_difficulty(args.share_bits),
+            next_nonce: rand::thread_rng().gen(),
+            range_size: args.range_size,
+            submitting: false,
+            seen_shares: HashSet::new(),
+            pplns_window: args.pplns_window,
+        }));
+        let ledger = match Ledger::load(Path::new(&args.ledger)) {
+            Ok(ledger) => Arc::new(Mutex::new(ledger)),
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            }
+        };
+        ledger
+            .lock()
+            .unwrap()
+            .credit(proof.total_rewards, args.payout_method);
+        let payout_threshold =
+            (args.payout_threshold * 10f64.powf(ore::TOKEN_DECIMALS as f64)) as u64;
+        let (challenge_tx, challenge_rx) = watch::channel(KeccakHash::from(proof.hash));
+        let (solution_tx, mut solution_rx) = mpsc::channel::<(KeccakHash, u64)>(16);
+
+        // Accept workers
+        let listener = match TcpListener::bind(&args.listen).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::error!("Failed to listen on {}: {}", args.listen, err);
+                return;
+            }
+        };
+        log::info!("Pool listening on {} for {}", args.listen, signer.pubkey());
+        tokio::spawn({
+            let state = state.clone();
+            let ledger = ledger.clone();
+            async move {
+                loop {
+                    let Ok((stream, addr)) = listener.accept().await else {
+                        continue;
+                    };
+                    log::info!("Worker connected: {}", addr);
+                    let state = state.clone();
+                    let ledger = ledger.clone();
+                    let challenge_rx = challenge_rx.clone();
+                    let solution_tx = solution_tx.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) =
+                            handle_worker(stream, addr, state, ledger, challenge_rx, solution_tx)
+                                .await
+                        {
+                            log::info!("Worker {} disconnected: {}", addr, err);
+                        }
+                    });
+                }
+            }
+        });
+
+        // Submit solutions alongside tracking the challenge and paying workers. A submission is
+        // abandoned once the challenge moves on.
+        let mut payouts = tokio::time::interval(Duration::from_secs(args.payout_interval));
+        payouts.tick().await;
+        let stale = AtomicBool::new(false);
+        let submission = Fuse::terminated();
+        tokio::pin!(submission);
+        loop {
+            tokio::select! {
+                Some((hash, nonce)) = solution_rx.recv(), if submission.is_terminated() => {
+                    log::info!("Submitting hash {} (nonce {})...", hash, nonce);
+                    stale.store(false, Ordering::Relaxed);
+                    submission.set(
+                        self.submit_hash(&signer, hash, nonce, self.priority_fee, 0, Some(&stale))
+                            .fuse(),
+                    );
+                }
+                submitted = &mut submission => {
+                    if submitted.signature.is_none() {
+                        log::warn!("Challenge changed, abandoned submitting hash");
+                    }
+                }
+                _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+                _ = payouts.tick() => {
+                    self.pool_payout(&ledger, payout_threshold).await;
+                }
+            }
+            let (proof, treasury) = match fetch_challenge(&client, signer.pubkey()).await {
+                Ok(accounts) => accounts,
+                Err(err) => {
+                    log::warn!("{}", err);
+                    continue;
+                }
+            };
+            {
+                let mut ledger = ledger.lock().unwrap();
+                ledger.credit(proof.total_rewards, args.payout_method);
+                ledger.flush();
+            }
+            let challenge = KeccakHash::from(proof.hash);
+            let difficulty = KeccakHash::from(treasury.difficulty);
+            let mut state = state.lock().unwrap();
+            if challenge.ne(&state.challenge) || difficulty.ne(&state.difficulty) {
+                log::info!("New challenge: {}", challenge);
+                state.challenge = challenge;
+                state.difficulty = difficulty;
+                state.next_nonce = rand::thread_rng().gen();
+                state.submitting = false;
+                state.seen_shares.clear();
+                challenge_tx.send(challenge).ok();
+                stale.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Fetches the pool's proof and the treasury, for the current challenge and difficulty.
+async fn fetch_challenge(
+    client: &RpcClient,
+    authority: Pubkey,
+) -> Result<(Proof, Treasury), String> {
+    let accounts = client
+        .get_multiple_accounts(&[proof_pubkey(authority), TREASURY_ADDRESS])
+        .await
+        .map_err(|err| format!("Failed to fetch proof and treasury: {}", err))?;
+    let proof = accounts[0]
+        .as_ref()
+        .and_then(|a| Proof::try_from_bytes(&a.data).ok().copied())
+        .ok_or("Proof account not found")?;
+    let treasury = accounts[1]
+        .as_ref()
+        .and_then(|a| Treasury::try_from_bytes(&a.data).ok().copied())
+        .ok_or("Treasury account not found")?;
+    Ok((proof, treasury))
+}
+
+async fn handle_worker(
+    stream: TcpStream,
+    addr: SocketAddr,
+    state: Arc<Mutex<PoolState>>,
+    ledger: Arc<Mutex<Ledger>>,
+    mut challenge_rx: watch::Receiver<KeccakHash>,
+    solution_tx: mpsc::Sender<(KeccakHash, u64)>,
+) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut name = addr.to_string();
+    let mut payout: Option<String> = None;
+    let work = state.lock().unwrap().next_work();
+    write_message(&mut writer, &work).await?;
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+                };
+                match serde_json::from_str::<WorkerMessage>(&line) {
+                    Ok(WorkerMessage::Hello { worker, payout: payout_address }) => {
+                        log::info!("Worker {} is {}", addr, worker);
+                        name = worker;
+                        payout = payout_address.filter(|p| Pubkey::from_str(p).is_ok());
+                        if payout.is_none() {
+                            log::warn!("Worker {} has no valid payout address, shares will not be credited", name);
+                        }
+                    }
+                    Ok(WorkerMessage::Share { challenge, nonce }) => {
+                        let result = {
+                            let mut state = state.lock().unwrap();
+                            let window = state.pplns_window;
+                            state.verify_share(&challenge, nonce).and_then(|_| match payout.as_ref() {
+                                Some(payout) => {
+                                    ledger.lock().unwrap().record_share(payout, window);
+                                    Ok(())
+                                }
+                                None => Err("No payout address".to_string()),
+                            })
+                        };
+                        if let Err(reason) = result {
+                            write_message(&mut writer, &ServerMessage::Rejected { nonce, reason })
+                                .await?;
+                        }
+                    }
+                    Ok(WorkerMessage::NextRange) => {
+                        let work = state.lock().unwrap().next_work();
+                        write_message(&mut writer, &work).await?;
+                    }
+                    Ok(WorkerMessage::Submit { challenge, nonce }) => {
+                        let result = {
+                            let mut state = state.lock().unwrap();
+                            let result = state.verify(&challenge, nonce);
+                            if result.is_ok() {
+                                state.submitting = true;
+                            }
+                            result
+                        };
+                        match result {
+                            Ok(hash) => {
+                                log::info!("Worker {} found a valid hash: {}", name, hash);
+                                solution_tx.send((hash, nonce)).await.ok();
+                                write_message(&mut writer, &ServerMessage::Accepted { nonce })
+                                    .await?;
+                            }
+                            Err(reason) => {
+                                write_message(
+                                    &mut writer,
+                                    &ServerMessage::Rejected { nonce, reason },
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        log::warn!("Invalid message from {}: {}", name, err);
+                    }
+                }
+            }
+            changed = challenge_rx.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let work = state.lock().unwrap().next_work();
+                write_message(&mut writer, &work).await?;
+            }
+        }
+    }
+}
+
+/// Builds a difficulty hash which requires `bits` leading zero bits.
+fn share_difficulty(bits: u32) -> KeccakHash {
+    let mut bytes = [255u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let bit = (i as u32).saturating_mul(8);
+        if bits >= bit.saturating_add(8) {
+            *byte = 0;
+        } else if bits > bit {
+            *byte = 255 >> (bits - bit);
+        }
+    }
+    KeccakHash::new_from_array(bytes)
+}
@@ -0,0 +1,176 @@
+use std::{
+    io,
+    str::FromStr,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use solana_program::{keccak::Hash as KeccakHash, pubkey::Pubkey};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::TcpStream,
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::{
+    mine::{find_hash_par, HashCounters, HashParams},
+    pool::{write_message, ServerMessage, WorkerMessage},
+    progress::Progress,
+    Miner, PoolWorkArgs,
+};
+
+// Delay before reconnecting to the pool server
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+struct Job {
+    challenge: String,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<(KeccakHash, u64)>>,
+}
+
+impl Miner {
+    pub async fn pool_work(&self, args: PoolWorkArgs) {
+        loop {
+            if let Err(err) = run_worker(&args).await {
+                log::warn!("Disconnected from {}: {}", args.server, err);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+}
+
+async fn run_worker(args: &PoolWorkArgs) -> io::Result<()> {
+    let stream = TcpStream::connect(&args.server).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    log::info!("Connected to {}", args.server);
+    write_message(
+        &mut writer,
+        &WorkerMessage::Hello {
+            worker: args.name.clone(),
+            payout: args.payout.clone(),
+        },
+    )
+    .await?;
+    let mut job: Option<Job> = None;
+    let mut current_challenge: Option<String> = None;
+    let (share_tx, mut share_rx) = mpsc::unbounded_channel::<u64>();
+    let counters = Arc::new(HashCounters::new(args.threads));
+    let progress = Progress::start(counters.clone());
+    let result = 'session: loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    Ok(None) => {
+                        break Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"))
+                    }
+                    Err(err) => break Err(err),
+                };
+                match serde_json::from_str::<ServerMessage>(&line) {
+                    Ok(ServerMessage::Work {
+                        challenge,
+                        authority,
+                        difficulty,
+                        share_difficulty,
+                        nonce_start,
+                        nonce_end,
+                    }) => {
+                        stop_job(job.take()).await;
+                        while let Ok(nonce) = share_rx.try_recv() {
+                            if let Some(current) = current_challenge.clone() {
+                                let share = WorkerMessage::Share { challenge: current, nonce };
+                                if let Err(err) = write_message(&mut writer, &share).await {
+                                    break 'session Err(err);
+                                }
+                            }
+                        }
+                        let (Ok(hash), Ok(authority), Ok(difficulty), Ok(share_difficulty)) = (
+                            KeccakHash::from_str(&challenge),
+                            Pubkey::from_str(&authority),
+                            KeccakHash::from_str(&difficulty),
+                            KeccakHash::from_str(&share_difficulty),
+                        ) else {
+                            log::warn!("Invalid work from server: {}", line);
+                            continue;
+                        };
+                        log::info!(
+                            "Mining nonces {}..{} for challenge {}",
+                            nonce_start, nonce_end, challenge
+                        );
+                        let stop = Arc::new(AtomicBool::new(false));
+                        let threads = args.threads;
+                        let params = HashParams {
+                            challenge: hash,
+                            authority,
+                            difficulty,
+                            nonce_start,
+                            nonce_end,
+                        };
+                        if current_challenge.as_ref().ne(&Some(&challenge)) {
+                            progress.start_challenge(difficulty);
+                        }
+                        let shares = args
+                            .payout
+                            .as_ref()
+                            .map(|_| (share_difficulty, share_tx.clone()));
+                        current_challenge = Some(challenge.clone());
+                        let handle = tokio::task::spawn_blocking({
+                            let stop = stop.clone();
+                            let counters = counters.clone();
+                            move || find_hash_par(params, threads, &counters, stop, shares).0
+                        });
+                        job = Some(Job {
+                            challenge,
+                            stop,
+                            handle,
+                        });
+                    }
+                    Ok(ServerMessage::Accepted { nonce }) => {
+                        log::info!("Nonce {} accepted", nonce);
+                    }
+                    Ok(ServerMessage::Rejected { nonce, reason }) => {
+                        log::warn!("Nonce {} rejected: {}", nonce, reason);
+                    }
+                    Err(err) => {
+                        log::warn!("Invalid message from server: {}", err);
+                    }
+                }
+            }
+            Some(nonce) = share_rx.recv() => {
+                let Some(challenge) = current_challenge.clone() else {
+                    continue;
+                };
+                if let Err(err) =
+                    write_message(&mut writer, &WorkerMessage::Share { challenge, nonce }).await
+                {
+                    break Err(err);
+                }
+            }
+            solution = async { (&mut job.as_mut().unwrap().handle).await }, if job.is_some() => {
+                let challenge = job.take().unwrap().challenge;
+                let message = match solution {
+                    Ok(Some((hash, nonce))) => {
+                        log::info!("Found hash {} (nonce {})", hash, nonce);
+                        WorkerMessage::Submit { challenge, nonce }
+                    }
+                    _ => WorkerMessage::NextRange,
+                };
+                if let Err(err) = write_message(&mut writer, &message).await {
+                    break Err(err);
+                }
+            }
+        }
+    };
+    stop_job(job).await;
+    progress.stop();
+    result
+}
+
+async fn stop_job(job: Option<Job>) {
+    if let Some(job) = job {
+        job.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        job.handle.await.ok();
+    }
+}
@@ -0,0 +1,97 @@
+use std::{path::PathBuf, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+// How long to wait for an HTTP price source
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the price of one ORE in SOL comes from.
+#[derive(Clone, Debug)]
+pub enum PriceSource {
+    Fixed(f64),
+    File(PathBuf),
+    Http(String),
+}
+
+/// Parses a price in SOL, an `http://` URL or a file path.
+pub fn parse_price_source(value: &str) -> Result<PriceSource, String> {
+    if let Ok(price) = value.parse::<f64>() {
+        return match price.is_finite() && price >= 0.0 {
+            true => Ok(PriceSource::Fixed(price)),
+            false => Err(format!("Invalid price: {}", value)),
+        };
+    }
+    if value.starts_with("https://") {
+        return Err("Only http:// price sources are supported".to_string());
+    }
+    match value.starts_with("http://") {
+        true => Ok(PriceSource::Http(value.to_string())),
+        false => Ok(PriceSource::File(PathBuf::from(value))),
+    }
+}
+
+impl PriceSource {
+    /// Returns the current price. Files and HTTP responses hold either a bare number or a JSON
+    /// object with a `price` field.
+    pub async fn fetch(&self) -> Result<f64, String> {
+        let body = match self {
+            PriceSource::Fixed(price) => return Ok(*price),
+            PriceSource::File(path) => tokio::fs::read_to_string(path)
+                .await
+                .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?,
+            PriceSource::Http(url) => tokio::time::timeout(HTTP_TIMEOUT, http_get(url))
+                .await
+                .map_err(|_| format!("Timed out fetching {}", url))?
+                .map_err(|err| format!("Failed to fetch {}: {}", url, err))?,
+        };
+        parse_price(&body).ok_or_else(|| format!("Invalid price: {}", body.trim()))
+    }
+}
+
+fn parse_price(body: &str) -> Option<f64> {
+    let price = match body.trim().parse::<f64>() {
+        Ok(price) => price,
+        Err(_) => {
+            let data: serde_json::Value = serde_json::from_str(body).ok()?;
+            match &data["price"] {
+                serde_json::Value::String(price) => price.parse().ok()?,
+                price => price.as_f64()?,
+            }
+        }
+    };
+    (price.is_finite() && price >= 0.0).then_some(price)
+}
+
+/// Fetches the body of a plain HTTP URL.
+async fn http_get(url: &str) -> std::io::Result<String> {
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    let rest = url.trim_start_matches("http://");
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let addr = match host.contains(':') {
+        true => host.to_string(),
+        false => format!("{}:80", host),
+    };
+    let mut stream = TcpStream::connect(addr).await?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json, text/plain\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| invalid("malformed response"))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(invalid(status));
+    }
+    Ok(body.to_string())
+}
@@ -0,0 +1,242 @@
+use ore::{
+    state::{Bus, Treasury},
+    utils::AccountDeserialize,
+    BUS_ADDRESSES, BUS_COUNT, BUS_EPOCH_REWARDS, EPOCH_DURATION, TREASURY_ADDRESS,
+};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{clock::Clock, sysvar};
+use solana_sdk::{commitment_config::CommitmentConfig, keccak::Hash as KeccakHash};
+
+use crate::{
+    cpu,
+    cu_limits::CU_LIMIT_MINE,
+    table::Table,
+    utils::{amount_u64_to_f64, estimate_fee, expected_hashes, format_hashrate, lamports_to_sol},
+    Miner, OutputFormat, ProfitabilityArgs,
+};
+
+/// Projected returns at a hashrate, assuming every solution lands. Rewards are capped by what the
+/// busses pay out, for the day by the epoch budget and until the next reset by what they hold.
+#[derive(Serialize)]
+struct Projection {
+    hashrate: f64,
+    benchmarked: bool,
+    difficulty: String,
+    expected_hashes: f64,
+    reward_rate_ore: f64,
+    bus_rewards_ore: f64,
+    priority_fee: u64,
+    cu_limit: u32,
+    fee_per_solution_sol: f64,
+    solutions_per_hour: f64,
+    seconds_until_reset: i64,
+    ore_until_reset: f64,
+    budget_ore_per_day: f64,
+    capped_by_budget: bool,
+    ore_per_day: f64,
+    fees_sol_per_day: f64,
+    break_even_ore_price_sol: Option<f64>,
+    ore_price_sol: Option<f64>,
+    revenue_sol_per_day: Option<f64>,
+    net_sol_per_day: Option<f64>,
+    sol_price_usd: Option<f64>,
+    net_usd_per_day: Option<f64>,
+}
+
+impl Miner {
+    pub async fn profitability(&self, args: ProfitabilityArgs) {
+        // Fetch the clock, difficulty, reward rate and bus rewards
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        let mut addresses = vec![sysvar::clock::ID, TREASURY_ADDRESS];
+        addresses.extend(BUS_ADDRESSES.iter());
+        let accounts = match client.get_multiple_accounts(&addresses).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                log::error!("Failed to fetch treasury and busses: {}", err);
+                return;
+            }
+        };
+        let Some(clock) = accounts[0]
+            .as_ref()
+            .and_then(|a| bincode::deserialize::<Clock>(&a.data).ok())
+        else {
+            log::error!("Failed to read the clock");
+            return;
+        };
+        let Some(treasury) = accounts[1]
+            .as_ref()
+            .and_then(|a| Treasury::try_from_bytes(&a.data).ok().copied())
+        else {
+            log::error!("Treasury account not found");
+            return;
+        };
+        let bus_rewards: u64 = accounts[2..]
+            .iter()
+            .filter_map(|a| a.as_ref())
+            .filter_map(|a| Bus::try_from_bytes(&a.data).ok())
+            .map(|bus| bus.rewards)
+            .sum();
+
+        // Fetch the price, if given
+        let ore_price = match args.ore_price.as_ref() {
+            Some(source) => match source.fetch().await {
+                Ok(price) => Some(price),
+                Err(err) => {
+                    log::error!("{}", err);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        // Measure the local hashrate, unless given
+        let (hashrate, benchmarked) = match args.hashrate {
+            Some(hashrate) => (hashrate, false),
+            None => {
+                let threads = args.threads.unwrap_or(num_cpus::get() as u64);
+                log::info!("Benchmarking {} threads...", threads);
+                let hashrate = tokio::task::spawn_blocking(move || cpu::measure_hashrate(threads))
+                    .await
+                    .unwrap();
+                (hashrate, true)
+            }
+        };
+
+        // Project
+        let difficulty = KeccakHash::from(treasury.difficulty);
+        let expected = expected_hashes(difficulty);
+        let reward = amount_u64_to_f64(treasury.reward_rate);
+        let fee = lamports_to_sol(estimate_fee(CU_LIMIT_MINE, self.priority_fee));
+        let solutions_per_hour = hashrate * 3600.0 / expected;
+
+        // No miner can earn more than the busses pay out, so cap rewards at the epoch budget for
+        // the day, and at what is left in the busses until the next reset
+        let budget_per_day = amount_u64_to_f64(BUS_EPOCH_REWARDS.saturating_mul(BUS_COUNT as u64))
+            * (86400 / EPOCH_DURATION) as f64;
+        let capped_by_budget = solutions_per_hour * 24.0 * reward > budget_per_day;
+        let ore_per_day = (solutions_per_hour * 24.0 * reward).min(budget_per_day);
+        let seconds_until_reset = treasury
+            .last_reset_at
+            .saturating_add(EPOCH_DURATION)
+            .saturating_sub(clock.unix_timestamp)
+            .max(0);
+        let ore_until_reset = (solutions_per_hour * seconds_until_reset as f64 / 3600.0 * reward)
+            .min(amount_u64_to_f64(bus_rewards));
+        if capped_by_budget {
+            log::warn!(
+                "Projected rewards exceed the {} ORE epoch budget per day, capping them",
+                budget_per_day
+            );
+        }
+
+        // Only landed solutions pay fees
+        let fees_per_day = if reward > 0.0 {
+            ore_per_day / reward * fee
+        } else {
+            solutions_per_hour * 24.0 * fee
+        };
+        let revenue = ore_price.map(|price| ore_per_day * price);
+        let net = revenue.map(|revenue| revenue - fees_per_day);
+        let projection = Projection {
+            hashrate,
+            benchmarked,
+            difficulty: difficulty.to_string(),
+            expected_hashes: expected,
+            reward_rate_ore: reward,
+            bus_rewards_ore: amount_u64_to_f64(bus_rewards),
+            priority_fee: self.priority_fee,
+            cu_limit: CU_LIMIT_MINE,
+            fee_per_solution_sol: fee,
+            solutions_per_hour,
+            seconds_until_reset,
+            ore_until_reset,
+            budget_ore_per_day: budget_per_day,
+            capped_by_budget,
+            ore_per_day,
+            fees_sol_per_day: fees_per_day,
+            break_even_ore_price_sol: (reward > 0.0).then_some(fee / reward),
+            ore_price_sol: ore_price,
+            revenue_sol_per_day: revenue,
+            net_sol_per_day: net,
+            sol_price_usd: args.sol_price,
+            net_usd_per_day: net.zip(args.sol_price).map(|(net, price)| net * price),
+        };
+
+        // Print
+        match args.format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&projection).unwrap());
+            }
+            OutputFormat::Table | OutputFormat::Csv => {
+                let p = &projection;
+                let optional = |value: Option<f64>, precision: usize| {
+                    value.map_or("-".to_string(), |v| format!("{:.*}", precision, v))
+                };
+                let mut table = Table::new(&["Metric", "Value"]);
+                for (metric, value) in [
+                    (
+                        "Hashrate",
+                        format!(
+                            "{}{}",
+                            format_hashrate(p.hashrate),
+                            if p.benchmarked { " (measured)" } else { "" }
+                        ),
+                    ),
+                    ("Difficulty", p.difficulty.clone()),
+                    ("Expected hashes", format!("{:.0}", p.expected_hashes)),
+                    ("Reward rate (ORE)", format!("{:.9}", p.reward_rate_ore)),
+                    (
+                        "Bus rewards left (ORE)",
+                        format!("{:.9}", p.bus_rewards_ore),
+                    ),
+                    ("Priority fee (microlamports)", p.priority_fee.to_string()),
+                    ("CU limit", p.cu_limit.to_string()),
+                    (
+                        "Fee per solution (SOL)",
+                        format!("{:.9}", p.fee_per_solution_sol),
+                    ),
+                    ("Solutions per hour", format!("{:.2}", p.solutions_per_hour)),
+                    (
+                        "ORE until next reset",
+                        format!("{:.9} in {}s", p.ore_until_reset, p.seconds_until_reset),
+                    ),
+                    (
+                        "Epoch budget per day (ORE)",
+                        format!("{:.9}", p.budget_ore_per_day),
+                    ),
+                    (
+                        "ORE per day",
+                        format!(
+                            "{:.9}{}",
+                            p.ore_per_day,
+                            if p.capped_by_budget {
+                                " (capped at the epoch budget)"
+                            } else {
+                                ""
+                            }
+                        ),
+                    ),
+                    ("Fees per day (SOL)", format!("{:.9}", p.fees_sol_per_day)),
+                    (
+                        "Break-even ORE price (SOL)",
+                        optional(p.break_even_ore_price_sol, 9),
+                    ),
+                    ("ORE price (SOL)", optional(p.ore_price_sol, 9)),
+                    ("Revenue per day (SOL)", optional(p.revenue_sol_per_day, 9)),
+                    ("Net per day (SOL)", optional(p.net_sol_per_day, 9)),
+                    ("SOL price (USD)", optional(p.sol_price_usd, 2)),
+                    ("Net per day (USD)", optional(p.net_usd_per_day, 2)),
+                ] {
+                    table.add_row(vec![metric.to_string(), value]);
+                }
+                if let OutputFormat::Csv = args.format {
+                    table.print_csv();
+                } else {
+                    table.print();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use solana_sdk::keccak::Hash as KeccakHash;
+
+use crate::{
+    logger,
+    mine::HashCounters,
+    utils::{expected_hashes, format_duration, format_hashrate},
+};
+
+// How often to sample the hash counters and redraw the progress line
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// How often to log the hashrate of each thread
+const LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tracks the hashrate of each thread and the progress made on the current challenge.
+pub struct Progress {
+    counters: Arc<HashCounters>,
+    state: Mutex<ProgressState>,
+    stopped: AtomicBool,
+}
+
+struct ProgressState {
+    rates: Vec<f64>,
+    challenge_hashes_start: u64,
+    expected_hashes: f64,
+}
+
+impl Progress {
+    /// Starts sampling `counters` on a background thread.
+    pub fn start(counters: Arc<HashCounters>) -> Arc<Self> {
+        let progress = Arc::new(Self {
+            state: Mutex::new(ProgressState {
+                rates: vec![0.0; counters.threads() as usize],
+                challenge_hashes_start: counters.total(),
+                expected_hashes: 0.0,
+            }),
+            counters,
+            stopped: AtomicBool::new(false),
+        });
+        std::thread::spawn({
+            let progress = progress.clone();
+            move || progress.run()
+        });
+        progress
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Resets the hashes counted towards a solution for a new challenge or difficulty.
+    pub fn start_challenge(&self, difficulty: KeccakHash) {
+        let mut state = self.state.lock().unwrap();
+        state.challenge_hashes_start = self.counters.total();
+        state.expected_hashes = expected_hashes(difficulty);
+    }
+
+    /// Returns the hashes per second of each thread over the last sample.
+    pub fn rates(&self) -> Vec<f64> {
+        self.state.lock().unwrap().rates.clone()
+    }
+
+    pub fn challenge_hashes(&self) -> u64 {
+        let start = self.state.lock().unwrap().challenge_hashes_start;
+        self.counters.total().saturating_sub(start)
+    }
+
+    pub fn expected_hashes(&self) -> f64 {
+        self.state.lock().unwrap().expected_hashes
+    }
+
+    /// Returns the time within which a solution is found with the given probability at the
+    /// current hashrate. Hashes are independent, so this does not depend on the hashes tried.
+    pub fn eta(&self, probability: f64) -> Option<Duration> {
+        let hashrate: f64 = self.rates().iter().sum();
+        let expected = self.expected_hashes();
+        if hashrate <= 0.0 || expected <= 0.0 {
+            return None;
+        }
+        let secs = -(1.0 - probability).ln() * expected / hashrate;
+        Some(Duration::from_secs_f64(secs.min(u32::MAX as f64)))
+    }
+
+    /// Formats the total hashrate, hashes tried this challenge and the ETA on one line.
+    pub fn summary(&self) -> String {
+        let hashrate: f64 = self.rates().iter().sum();
+        let hashes = self.challenge_hashes();
+        let expected = self.expected_hashes();
+        let mut summary = format!("{}  {} hashes", format_hashrate(hashrate), hashes);
+        if expected > 0.0 {
+            summary.push_str(&format!(
+                " ({:.0}% of {:.0} expected)",
+                hashes as f64 / expected * 100.0,
+                expected
+            ));
+        }
+        if let (Some(median), Some(likely)) = (self.eta(0.5), self.eta(0.9)) {
+            summary.push_str(&format!(
+                "  ETA {} (50%), {} (90%)",
+                format_duration(median),
+                format_duration(likely)
+            ));
+        }
+        summary
+    }
+
+    fn run(&self) {
+        let mut last_sample = (Instant::now(), self.counters.per_thread());
+        let mut last_log = Instant::now();
+        while !self.stopped.load(Ordering::Relaxed) {
+            std::thread::sleep(SAMPLE_INTERVAL);
+            let sample = (Instant::now(), self.counters.per_thread());
+            let secs = sample.0.duration_since(last_sample.0).as_secs_f64();
+            let rates: Vec<f64> = sample
+                .1
+                .iter()
+                .zip(last_sample.1.iter())
+                .map(|(now, before)| now.saturating_sub(*before) as f64 / secs)
+                .collect();
+            let hashing = rates.iter().any(|rate| *rate > 0.0);
+            self.state.lock().unwrap().rates = rates;
+            last_sample = sample;
+            if !hashing {
+                continue;
+            }
+            logger::progress(&self.summary());
+            if last_log.elapsed() >= LOG_INTERVAL {
+                last_log = Instant::now();
+                let threads: Vec<String> = self
+                    .rates()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, rate)| **rate > 0.0)
+                    .map(|(i, rate)| format!("{}: {}", i, format_hashrate(*rate)))
+                    .collect();
+                log::info!("{}  threads {}", self.summary(), threads.join(", "));
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use ore::{state::Proof, utils::AccountDeserialize};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{keccak::Hash as KeccakHash, pubkey::Pubkey};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signer};
+
+use crate::{
+    table::Table,
+    utils::{amount_u64_to_f64, proof_pubkey},
+    Miner, OutputFormat, ProofArgs,
+};
+
+#[derive(Serialize)]
+struct ProofView {
+    address: String,
+    derived_address: String,
+    authority: String,
+    hash: String,
+    claimable_rewards: f64,
+    total_hashes: u64,
+    total_rewards: f64,
+}
+
+impl ProofView {
+    fn new(address: Pubkey, proof: &Proof) -> Self {
+        Self {
+            address: address.to_string(),
+            derived_address: proof_pubkey(proof.authority).to_string(),
+            authority: proof.authority.to_string(),
+            hash: KeccakHash::from(proof.hash).to_string(),
+            claimable_rewards: amount_u64_to_f64(proof.claimable_rewards),
+            total_hashes: proof.total_hashes,
+            total_rewards: amount_u64_to_f64(proof.total_rewards),
+        }
+    }
+}
+
+impl Miner {
+    pub async fn proof(&self, args: ProofArgs) {
+        let address = match args.address {
+            Some(address) => match Pubkey::from_str(&address) {
+                Ok(address) => address,
+                Err(_) => {
+                    log::error!("Invalid address: {:?}", address);
+                    return;
+                }
+            },
+            None => self.signer().pubkey(),
+        };
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+
+        // The address is either a proof, or the authority of one
+        let Some((address, proof)) = fetch_proof(&client, address).await else {
+            return;
+        };
+        let view = ProofView::new(address, &proof);
+        match args.format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&view).unwrap());
+            }
+            OutputFormat::Table | OutputFormat::Csv => {
+                let mut table = Table::new(&["Field", "Value"]);
+                for (field, value) in [
+                    ("Address", view.address.clone()),
+                    ("Derived address", view.derived_address.clone()),
+                    ("Authority", view.authority.clone()),
+                    ("Hash", view.hash.clone()),
+                    ("Claimable (ORE)", format!("{:.9}", view.claimable_rewards)),
+                    ("Total hashes", view.total_hashes.to_string()),
+                    ("Total rewards (ORE)", format!("{:.9}", view.total_rewards)),
+                ] {
+                    table.add_row(vec![field.to_string(), value]);
+                }
+                if let OutputFormat::Csv = args.format {
+                    table.print_csv();
+                } else {
+                    table.print();
+                }
+            }
+        }
+        if view.address.ne(&view.derived_address) {
+            log::warn!("Proof address does not match the address derived from its authority");
+        }
+        if !args.watch {
+            return;
+        }
+
+        // Print a line each time the hash advances
+        let mut last = proof;
+        let mut interval = tokio::time::interval(args.interval);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let proof = match client.get_account_data(&address).await {
+                Ok(data) => match Proof::try_from_bytes(&data) {
+                    Ok(proof) => *proof,
+                    Err(_) => {
+                        log::error!("Account {} is no longer a proof", address);
+                        return;
+                    }
+                },
+                Err(err) => {
+                    log::warn!("Failed to fetch proof: {}", err);
+                    continue;
+                }
+            };
+            if proof.hash.eq(&last.hash) {
+                continue;
+            }
+            let view = ProofView::new(address, &proof);
+            let reward = proof.total_rewards.saturating_sub(last.total_rewards);
+            match args.format {
+                OutputFormat::Json => {
+                    let mut line = serde_json::to_value(&view).unwrap();
+                    line["timestamp"] = chrono::Utc::now().timestamp().into();
+                    line["reward"] = amount_u64_to_f64(reward).into();
+                    println!("{}", line);
+                }
+                OutputFormat::Table | OutputFormat::Csv => {
+                    println!(
+                        "{}  {}  +{:.9} ORE  {} total hashes  {:.9} ORE claimable",
+                        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"),
+                        view.hash,
+                        amount_u64_to_f64(reward),
+                        view.total_hashes,
+                        view.claimable_rewards
+                    );
+                }
+            }
+            last = proof;
+        }
+    }
+}
+
+/// Fetches the proof at `address`, or else the proof of the authority `address`.
+async fn fetch_proof(client: &RpcClient, address: Pubkey) -> Option<(Pubkey, Proof)> {
+    let addresses = [address, proof_pubkey(address)];
+    let accounts = match client.get_multiple_accounts(&addresses).await {
+        Ok(accounts) => accounts,
+        Err(err) => {
+            log::error!("Error fetching accounts: {:?}", err);
+            return None;
+        }
+    };
+    let proof = accounts
+        .iter()
+        .zip(addresses)
+        .find_map(|(account, address)| {
+            let account = account.as_ref()?;
+            if account.owner.ne(&ore::ID) {
+                return None;
+            }
+            Some((address, *Proof::try_from_bytes(&account.data).ok()?))
+        });
+    if proof.is_none() {
+        log::error!("No proof found for {}", address);
+    }
+    proof
+}
@@ -18,7 +18,7 @@ impl Miner {
         }
 
         // Sign and send transaction.
-        println!("Generating challenge...");
+        log::info!("Generating challenge...");
         let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_REGISTER);
         let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
         let ix = ore::instruction::register(signer.pubkey());
@@ -11,7 +11,7 @@ impl Miner {
             if let Ok(address) = Pubkey::from_str(&address) {
                 address
             } else {
-                println!("Invalid address: {:?}", address);
+                log::error!("Invalid address: {:?}", address);
                 return;
             }
         } else {
@@ -0,0 +1,234 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+// Monday first, as numbered by chrono
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// How far ahead to look for the next window, a week and a day
+const MAX_LOOKAHEAD_MINUTES: i64 = 8 * 24 * 60;
+
+/// A daily window during which mining is allowed, in local time. A window that ends before it
+/// starts runs past midnight, and belongs to the day it starts on.
+#[derive(Clone, Debug)]
+pub struct Window {
+    days: [bool; 7],
+    start: u32,
+    end: u32,
+}
+
+impl Window {
+    fn contains(&self, time: DateTime<Local>) -> bool {
+        let day = time.weekday().num_days_from_monday() as usize;
+        let minute = time.hour() * 60 + time.minute();
+        if self.start < self.end {
+            self.days[day] && self.start <= minute && minute < self.end
+        } else {
+            (self.days[day] && minute >= self.start)
+                || (self.days[(day + 6) % 7] && minute < self.end)
+        }
+    }
+}
+
+/// Parses windows like `22:00-06:00`, `mon-fri 18:00-08:00` or `sat,sun 00:00-24:00`.
+pub fn parse_window(value: &str) -> Result<Window, String> {
+    let (days, times) = match value.trim().rsplit_once(' ') {
+        Some((days, times)) => (parse_days(days.trim())?, times),
+        None => ([true; 7], value.trim()),
+    };
+    let (start, end) = times
+        .split_once('-')
+        .ok_or_else(|| format!("Invalid window: {}", value))?;
+    let (start, end) = (parse_time(start)?, parse_time(end)?);
+    if start == end {
+        return Err(format!("Empty window: {}", value));
+    }
+    Ok(Window {
+        days,
+        start,
+        end: end % (24 * 60),
+    })
+}
+
+fn parse_days(value: &str) -> Result<[bool; 7], String> {
+    let day = |name: &str| {
+        DAYS.iter()
+            .position(|day| name.to_lowercase().starts_with(day))
+            .ok_or_else(|| format!("Invalid day: {}", name))
+    };
+    let mut days = [false; 7];
+    for part in value.split(',').map(str::trim) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                let mut i = first;
+                loop {
+                    days[i] = true;
+                    if i == last {
+                        break;
+                    }
+                    i = (i + 1) % 7;
+                }
+            }
+            None => days[day(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+/// Parses `HH:MM` into minutes since midnight. `24:00` is the end of the day.
+fn parse_time(value: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid time: {}", value);
+    let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+    if minute >= 60 || hour > 24 || (hour == 24 && minute > 0) {
+        return Err(invalid());
+    }
+    Ok(hour * 60 + minute)
+}
+
+/// The windows during which mining is allowed. An empty schedule is always open.
+pub struct Schedule {
+    windows: Vec<Window>,
+}
+
+impl Schedule {
+    pub fn new(windows: Vec<Window>) -> Self {
+        Self { windows }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open_at(Local::now())
+    }
+
+    fn is_open_at(&self, time: DateTime<Local>) -> bool {
+        self.windows.is_empty() || self.windows.iter().any(|window| window.contains(time))
+    }
+
+    /// Returns when the next window opens, to the minute.
+    pub fn next_open(&self) -> Option<DateTime<Local>> {
+        let now = Local::now();
+        let now = now - chrono::Duration::seconds(now.second() as i64);
+        (1..=MAX_LOOKAHEAD_MINUTES)
+            .map(|minutes| now + chrono::Duration::minutes(minutes))
+            .find(|time| self.is_open_at(*time))
+    }
+
+    /// Waits until a window opens. Returns false if `stop` is set first.
+    pub async fn wait(&self, stop: &AtomicBool) -> bool {
+        if self.is_open() {
+            return true;
+        }
+        match self.next_open() {
+            Some(time) => log::info!(
+                "Outside the mining schedule, pausing until {}",
+                time.format("%a %H:%M")
+            ),
+            None => log::info!("Outside the mining schedule, pausing"),
+        }
+        while !self.is_open() {
+            if stop.load(Ordering::Relaxed) {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        log::info!("Mining schedule open, resuming");
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    // 2024-01-01 was a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("00:00"), Ok(0));
+        assert_eq!(parse_time("06:30"), Ok(390));
+        assert_eq!(parse_time("24:00"), Ok(1440));
+        assert!(parse_time("24:01").is_err());
+        assert!(parse_time("12:60").is_err());
+        assert!(parse_time("1200").is_err());
+    }
+
+    #[test]
+    fn parses_days() {
+        assert_eq!(
+            parse_days("mon-fri"),
+            Ok([true, true, true, true, true, false, false])
+        );
+        assert_eq!(
+            parse_days("sat,Sunday"),
+            Ok([false, false, false, false, false, true, true])
+        );
+        assert_eq!(
+            parse_days("fri-mon"),
+            Ok([true, false, false, false, true, true, true])
+        );
+        assert!(parse_days("someday").is_err());
+    }
+
+    #[test]
+    fn overnight_window_wraps_past_midnight() {
+        let window = parse_window("22:00-06:00").unwrap();
+        assert!(window.contains(at(1, 23, 0)));
+        assert!(window.contains(at(2, 5, 59)));
+        assert!(!window.contains(at(2, 6, 0)));
+        assert!(!window.contains(at(2, 21, 59)));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_its_start_day() {
+        // Friday night into Saturday morning, but not Sunday night
+        let window = parse_window("fri 22:00-06:00").unwrap();
+        assert!(window.contains(at(5, 23, 0)));
+        assert!(window.contains(at(6, 3, 0)));
+        assert!(!window.contains(at(4, 23, 0)));
+        assert!(!window.contains(at(5, 3, 0)));
+    }
+
+    #[test]
+    fn window_until_midnight() {
+        let window = parse_window("sat,sun 00:00-24:00").unwrap();
+        assert!(window.contains(at(6, 0, 0)));
+        assert!(window.contains(at(7, 23, 59)));
+        assert!(!window.contains(at(8, 0, 0)));
+        assert!(!window.contains(at(5, 23, 59)));
+
+        let window = parse_window("18:00-24:00").unwrap();
+        assert!(window.contains(at(1, 23, 59)));
+        assert!(!window.contains(at(2, 0, 0)));
+        assert!(!window.contains(at(1, 17, 59)));
+    }
+
+    #[test]
+    fn wrapping_day_range() {
+        let window = parse_window("fri-mon 09:00-17:00").unwrap();
+        for day in [5, 6, 7, 8] {
+            assert!(window.contains(at(day, 12, 0)));
+        }
+        for day in [2, 3, 4] {
+            assert!(!window.contains(at(day, 12, 0)));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_windows() {
+        assert!(parse_window("10:00-10:00").is_err());
+        assert!(parse_window("10:00").is_err());
+        assert!(parse_window("someday 10:00-12:00").is_err());
+    }
+}
@@ -1,7 +1,4 @@
-use std::{
-    io::{stdout, Write},
-    time::Duration,
-};
+use std::time::Duration;
 
 use solana_client::{
     client_error::{ClientError, ClientErrorKind, Result as ClientResult},
@@ -11,7 +8,7 @@ use solana_client::{
 use solana_program::instruction::Instruction;
 use solana_sdk::{
     commitment_config::{CommitmentConfig, CommitmentLevel},
-    signature::{Signature, Signer},
+    signature::{Keypair, Signature, Signer},
     transaction::Transaction,
 };
 use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
@@ -28,17 +25,28 @@ impl Miner {
         ixs: &[Instruction],
         skip_confirm: bool,
     ) -> ClientResult<Signature> {
-        let mut stdout = stdout();
         let signer = self.signer();
+        self.send_and_confirm_with_signers(ixs, &[&signer], skip_confirm)
+            .await
+    }
+
+    /// Sends a transaction signed by every keypair in `signers`. The first signer pays the fees.
+    pub async fn send_and_confirm_with_signers(
+        &self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+        skip_confirm: bool,
+    ) -> ClientResult<Signature> {
+        let payer = signers[0];
         let client =
             RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
 
         // Return error if balance is zero
         let balance = client
-            .get_balance_with_commitment(&signer.pubkey(), CommitmentConfig::confirmed())
+            .get_balance_with_commitment(&payer.pubkey(), CommitmentConfig::confirmed())
             .await
             .unwrap();
-        if balance.value <= 0 {
+        if balance.value == 0 {
             return Err(ClientError {
                 request: None,
                 kind: ClientErrorKind::Custom("Insufficient SOL balance".into()),
@@ -57,18 +65,18 @@ impl Miner {
             max_retries: Some(RPC_RETRIES),
             min_context_slot: Some(slot),
         };
-        let mut tx = Transaction::new_with_payer(ixs, Some(&signer.pubkey()));
-        tx.sign(&[&signer], hash);
+        let mut tx = Transaction::new_with_payer(ixs, Some(&payer.pubkey()));
+        tx.sign(signers, hash);
 
         // Submit tx
         let mut sigs = vec![];
         let mut attempts = 0;
         loop {
-            println!("Attempt: {:?}", attempts);
+            log::debug!("Attempt: {:?}", attempts);
             match client.send_transaction_with_config(&tx, send_cfg).await {
                 Ok(sig) => {
                     sigs.push(sig);
-                    println!("{:?}", sig);
+                    log::debug!("Sent {:?}", sig);
 
                     // Confirm tx
                     if skip_confirm {
@@ -78,7 +86,7 @@ impl Miner {
                         std::thread::sleep(Duration::from_millis(2000));
                         match client.get_signature_statuses(&sigs).await {
                             Ok(signature_statuses) => {
-                                println!("Confirms: {:?}", signature_statuses.value);
+                                log::trace!("Confirms: {:?}", signature_statuses.value);
                                 for signature_status in signature_statuses.value {
                                     if let Some(signature_status) = signature_status.as_ref() {
                                         if signature_status.confirmation_status.is_some() {
@@ -90,12 +98,12 @@ impl Miner {
                                                 TransactionConfirmationStatus::Processed => {}
                                                 TransactionConfirmationStatus::Confirmed
                                                 | TransactionConfirmationStatus::Finalized => {
-                                                    println!("Transaction landed!");
+                                                    log::info!("Transaction landed!");
                                                     return Ok(sig);
                                                 }
                                             }
                                         } else {
-                                            println!("No status");
+                                            log::trace!("No status");
                                         }
                                     }
                                 }
@@ -103,20 +111,18 @@ impl Miner {
 
                             // Handle confirmation errors
                             Err(err) => {
-                                println!("Error: {:?}", err);
+                                log::warn!("Error: {:?}", err);
                             }
                         }
                     }
-                    println!("Transaction did not land");
+                    log::info!("Transaction did not land");
                 }
 
                 // Handle submit errors
                 Err(err) => {
-                    println!("Error {:?}", err);
+                    log::warn!("Error {:?}", err);
                 }
             }
-            stdout.flush().ok();
-
             // Retry
             std::thread::sleep(Duration::from_millis(200));
             (hash, slot) = client
@@ -130,7 +136,7 @@ impl Miner {
                 max_retries: Some(RPC_RETRIES),
                 min_context_slot: Some(slot),
             };
-            tx.sign(&[&signer], hash);
+            tx.sign(signers, hash);
             attempts += 1;
             if attempts > GATEWAY_RETRIES {
                 return Err(ClientError {
@@ -0,0 +1,216 @@
+use std::{
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::json;
+use solana_program::{keccak::Hash as KeccakHash, pubkey::Pubkey};
+
+use crate::{
+    claim::Claimed,
+    logger,
+    utils::{amount_u64_to_f64, format_duration, lamports_to_sol, state_dir},
+    MineArgs,
+};
+
+/// Running totals for a single `mine` invocation.
+pub struct Session {
+    pub started_at: Instant,
+    pub hashes: u64,
+    pub solutions: u64,
+    pub transactions_sent: u64,
+    pub transactions_landed: u64,
+    pub fees_spent: u64,
+    pub ore_earned: u64,
+    pub claims: Vec<Claimed>,
+    pub sol_spent: u64,
+    start_total_rewards: Option<u64>,
+    start_sol_balance: Option<u64>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            hashes: 0,
+            solutions: 0,
+            transactions_sent: 0,
+            transactions_landed: 0,
+            fees_spent: 0,
+            ore_earned: 0,
+            claims: vec![],
+            sol_spent: 0,
+            start_total_rewards: None,
+            start_sol_balance: None,
+        }
+    }
+
+    /// Updates the ORE earned this session from the proof's lifetime rewards.
+    pub fn update_rewards(&mut self, total_rewards: u64) {
+        let start = *self.start_total_rewards.get_or_insert(total_rewards);
+        self.ore_earned = total_rewards.saturating_sub(start);
+    }
+
+    /// Updates the SOL spent this session from the signer's balance. Measuring the drop in balance
+    /// covers every fee paid, including failed attempts, resets and claims.
+    pub fn update_sol_balance(&mut self, balance: u64) {
+        let start = *self.start_sol_balance.get_or_insert(balance);
+        self.sol_spent = start.saturating_sub(balance);
+    }
+
+    /// Returns the reason the session should end, if any of the stop conditions have been met.
+    pub fn stop_reason(&self, args: &MineArgs) -> Option<String> {
+        if let Some(until_ore) = args.until_ore {
+            let until_ore = (until_ore * 10f64.powf(ore::TOKEN_DECIMALS as f64)) as u64;
+            if self.ore_earned >= until_ore {
+                return Some(format!("Earned {} ORE", amount_u64_to_f64(self.ore_earned)));
+            }
+        }
+        if let Some(max_solutions) = args.max_solutions {
+            if self.solutions >= max_solutions {
+                return Some(format!("Found {} solutions", self.solutions));
+            }
+        }
+        if let Some(duration) = args.duration {
+            if self.started_at.elapsed() >= duration {
+                return Some(format!("Mined for {}", format_duration(duration)));
+            }
+        }
+        if let Some(max_sol_spent) = args.max_sol_spent {
+            if lamports_to_sol(self.sol_spent) >= max_sol_spent {
+                return Some(format!("Spent {} SOL", lamports_to_sol(self.sol_spent)));
+            }
+        }
+        None
+    }
+
+    pub fn record_claim(&mut self, claimed: Claimed, fee: u64) {
+        self.claims.push(claimed);
+        self.fees_spent += fee;
+    }
+
+    /// Writes the summary to stderr, so it is shown at any log level and stays out of events on
+    /// stdout. With JSON logs, it is written as a single record.
+    pub fn print_summary(&self) {
+        if logger::json() {
+            let record = json!({
+                "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                "level": "info",
+                "target": env!("CARGO_BIN_NAME"),
+                "message": "Session summary",
+                "summary": self.to_json(),
+            });
+            eprintln!("{}", record);
+            return;
+        }
+        let elapsed = self.started_at.elapsed();
+        eprintln!("Session summary");
+        eprintln!("  Duration: {}", format_duration(elapsed));
+        eprintln!("  Hashes: {}", self.hashes);
+        eprintln!(
+            "  Hashrate: {:.0} H/s",
+            self.hashes as f64 / elapsed.max(Duration::from_secs(1)).as_secs_f64()
+        );
+        eprintln!("  Solutions: {}", self.solutions);
+        eprintln!(
+            "  Transactions: {} landed, {} sent",
+            self.transactions_landed, self.transactions_sent
+        );
+        eprintln!(
+            "  Fees spent: {} SOL (estimated)",
+            lamports_to_sol(self.fees_spent)
+        );
+        eprintln!("  SOL spent: {} SOL", lamports_to_sol(self.sol_spent));
+        eprintln!("  ORE earned: {} ORE", amount_u64_to_f64(self.ore_earned));
+        if !self.claims.is_empty() {
+            eprintln!(
+                "  Claimed: {} ORE in {} claims",
+                amount_u64_to_f64(self.claims.iter().map(|claim| claim.amount).sum()),
+                self.claims.len()
+            );
+            for claim in self.claims.iter() {
+                eprintln!(
+                    "    {} ORE to {} ({})",
+                    amount_u64_to_f64(claim.amount),
+                    claim.beneficiary,
+                    claim.signature
+                );
+            }
+        }
+    }
+
+    pub fn write_json(&self, path: &str) {
+        let summary = self.to_json();
+        if let Err(err) = fs::write(path, serde_json::to_string_pretty(&summary).unwrap()) {
+            log::warn!("Failed to write {}: {}", path, err);
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let claims: Vec<_> = self
+            .claims
+            .iter()
+            .map(|claim| {
+                json!({
+                    "beneficiary": claim.beneficiary.to_string(),
+                    "amount_ore": amount_u64_to_f64(claim.amount),
+                    "signature": claim.signature.to_string(),
+                })
+            })
+            .collect();
+        json!({
+            "duration_secs": self.started_at.elapsed().as_secs(),
+            "hashes": self.hashes,
+            "solutions": self.solutions,
+            "transactions_sent": self.transactions_sent,
+            "transactions_landed": self.transactions_landed,
+            "fees_spent_lamports": self.fees_spent,
+            "sol_spent_lamports": self.sol_spent,
+            "ore_earned": amount_u64_to_f64(self.ore_earned),
+            "claims": claims,
+        })
+    }
+}
+
+/// A valid hash which was found but not submitted before the miner shut down.
+pub struct PendingSolution {
+    pub authority: Pubkey,
+    pub challenge: KeccakHash,
+    pub hash: KeccakHash,
+    pub nonce: u64,
+}
+
+impl PendingSolution {
+    fn path(authority: Pubkey) -> PathBuf {
+        state_dir().join(format!("pending-{}.json", authority))
+    }
+
+    pub fn save(&self) {
+        let path = Self::path(self.authority);
+        let data = json!({
+            "challenge": self.challenge.to_string(),
+            "hash": self.hash.to_string(),
+            "nonce": self.nonce,
+        });
+        if let Err(err) = fs::write(&path, data.to_string()) {
+            log::warn!("Failed to write {}: {}", path.display(), err);
+        }
+    }
+
+    /// Loads and removes the pending solution of the given authority, if there is one.
+    pub fn take(authority: Pubkey) -> Option<Self> {
+        let path = Self::path(authority);
+        let data = fs::read_to_string(&path).ok()?;
+        fs::remove_file(&path).ok();
+        let data: serde_json::Value = serde_json::from_str(&data).ok()?;
+        Some(Self {
+            authority,
+            challenge: KeccakHash::from_str(data["challenge"].as_str()?).ok()?,
+            hash: KeccakHash::from_str(data["hash"].as_str()?).ok()?,
+            nonce: data["nonce"].as_u64()?,
+        })
+    }
+}
@@ -0,0 +1,167 @@
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{
+    history::History,
+    table::Table,
+    utils::{amount_u64_to_f64, lamports_to_sol},
+    Miner, OutputFormat, StatsArgs,
+};
+
+#[derive(Serialize)]
+struct StatsRow {
+    day: String,
+    authority: String,
+    solutions: u64,
+    unsubmitted: u64,
+    transactions_sent: u64,
+    transactions_failed: u64,
+    landing_rate: f64,
+    hashes: u64,
+    hashrate: f64,
+    avg_solve_secs: f64,
+    avg_landing_secs: f64,
+    fees_sol: f64,
+    ore_earned: f64,
+    sol_per_ore: Option<f64>,
+}
+
+impl Miner {
+    pub async fn stats(&self, args: StatsArgs) {
+        let history = match History::open() {
+            Ok(history) => history,
+            Err(err) => {
+                log::error!("Failed to open {}: {}", History::path().display(), err);
+                return;
+            }
+        };
+        let since = chrono::Utc::now().timestamp() - (args.days as i64).saturating_mul(86400);
+
+        // Aggregate landed solutions and every attempt by day and authority
+        let stmt = history.conn().prepare(
+            "SELECT day, authority, SUM(solutions), SUM(unsubmitted), SUM(sent), SUM(landed),
+                SUM(failed), SUM(hashes), SUM(hashing_secs), SUM(solve_secs), SUM(landing_secs),
+                SUM(fee_lamports), SUM(ore_earned)
+            FROM (
+                SELECT date(timestamp, 'unixepoch') AS day, authority, 1 AS solutions,
+                    0 AS unsubmitted, 0 AS sent, 0 AS landed, 0 AS failed, hashes,
+                    CASE WHEN hashes > 0 THEN solve_secs ELSE 0 END AS hashing_secs, solve_secs,
+                    landing_secs, 0 AS fee_lamports, ore_earned
+                FROM solutions
+                WHERE timestamp >= ?1 AND (?2 IS NULL OR authority = ?2)
+                UNION ALL
+                SELECT date(timestamp, 'unixepoch'), authority, 0, kind = 'solution',
+                    kind = 'mine', kind = 'mine' AND outcome = 'landed',
+                    kind = 'mine' AND outcome = 'failed', 0, 0, 0, 0, fee_lamports, 0
+                FROM attempts
+                WHERE timestamp >= ?1 AND (?2 IS NULL OR authority = ?2)
+            )
+            GROUP BY day, authority
+            ORDER BY day, authority",
+        );
+        let mut stmt = match stmt {
+            Ok(stmt) => stmt,
+            Err(err) => {
+                log::error!("Failed to query mining history: {}", err);
+                return;
+            }
+        };
+        let rows = stmt
+            .query_map(params![since, args.authority], |row| {
+                let solutions: u64 = row.get(2)?;
+                let unsubmitted: u64 = row.get(3)?;
+                let transactions_sent: u64 = row.get(4)?;
+                let transactions_landed: u64 = row.get(5)?;
+                let transactions_failed: u64 = row.get(6)?;
+                let hashes: u64 = row.get(7)?;
+                let hashing_secs: f64 = row.get(8)?;
+                let solve_secs: f64 = row.get(9)?;
+                let landing_secs: f64 = row.get(10)?;
+                let fees_sol = lamports_to_sol(row.get(11)?);
+                let ore_earned = amount_u64_to_f64(row.get(12)?);
+                Ok(StatsRow {
+                    day: row.get(0)?,
+                    authority: row.get(1)?,
+                    solutions,
+                    unsubmitted,
+                    transactions_sent,
+                    transactions_failed,
+                    landing_rate: transactions_landed as f64 / transactions_sent.max(1) as f64,
+                    hashes,
+                    hashrate: if hashing_secs > 0.0 {
+                        hashes as f64 / hashing_secs
+                    } else {
+                        0.0
+                    },
+                    avg_solve_secs: solve_secs / solutions.max(1) as f64,
+                    avg_landing_secs: landing_secs / solutions.max(1) as f64,
+                    fees_sol,
+                    ore_earned,
+                    sol_per_ore: if ore_earned > 0.0 {
+                        Some(fees_sol / ore_earned)
+                    } else {
+                        None
+                    },
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>());
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(err) => {
+                log::error!("Failed to read mining history: {}", err);
+                return;
+            }
+        };
+
+        // Print
+        match args.format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+            }
+            OutputFormat::Table | OutputFormat::Csv => {
+                let mut table = Table::new(&[
+                    "Day",
+                    "Authority",
+                    "Solutions",
+                    "Unsubmitted",
+                    "Txs sent",
+                    "Txs failed",
+                    "Landing rate",
+                    "Hashes",
+                    "Hashrate (H/s)",
+                    "Avg solve (s)",
+                    "Avg landing (s)",
+                    "Fees (SOL)",
+                    "ORE earned",
+                    "SOL per ORE",
+                ]);
+                for row in rows.iter() {
+                    table.add_row(vec![
+                        row.day.clone(),
+                        row.authority.clone(),
+                        row.solutions.to_string(),
+                        row.unsubmitted.to_string(),
+                        row.transactions_sent.to_string(),
+                        row.transactions_failed.to_string(),
+                        format!("{:.1}%", row.landing_rate * 100.0),
+                        row.hashes.to_string(),
+                        format!("{:.0}", row.hashrate),
+                        format!("{:.1}", row.avg_solve_secs),
+                        format!("{:.1}", row.avg_landing_secs),
+                        format!("{:.9}", row.fees_sol),
+                        format!("{:.9}", row.ore_earned),
+                        row.sol_per_ore
+                            .map_or("-".to_string(), |cost| format!("{:.9}", cost)),
+                    ]);
+                }
+                if let OutputFormat::Csv = args.format {
+                    table.print_csv();
+                } else if rows.is_empty() {
+                    println!("No mining history in the last {} days", args.days);
+                } else {
+                    table.print();
+                }
+            }
+        }
+    }
+}
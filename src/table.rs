@@ -0,0 +1,84 @@
+use std::io::{stdout, IsTerminal};
+
+use crossterm::style::{Color, Stylize};
+
+/// A minimal text table for command output, which can also be rendered as CSV.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    colors: Vec<Option<Color>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: vec![],
+            colors: vec![],
+        }
+    }
+
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+        self.colors.push(None);
+    }
+
+    /// Adds a row which is drawn in `color` when printed to a terminal.
+    pub fn add_highlighted_row(&mut self, row: Vec<String>, color: Color) {
+        self.rows.push(row);
+        self.colors.push(Some(color));
+    }
+
+    pub fn print(&self) {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.len()).collect();
+        for row in self.rows.iter() {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+        let format_row = |row: &Vec<String>| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{:<width$}", cell, width = widths[i]))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+        println!("{}", format_row(&self.headers));
+        println!(
+            "{}",
+            widths
+                .iter()
+                .map(|w| "-".repeat(*w))
+                .collect::<Vec<_>>()
+                .join("  ")
+        );
+        let styled = stdout().is_terminal();
+        for (row, color) in self.rows.iter().zip(self.colors.iter()) {
+            match color {
+                Some(color) if styled => println!("{}", format_row(row).with(*color)),
+                _ => println!("{}", format_row(row)),
+            }
+        }
+    }
+
+    pub fn print_csv(&self) {
+        let format_row = |row: &Vec<String>| {
+            row.iter()
+                .map(|cell| {
+                    if cell.contains(',') || cell.contains('"') {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        println!("{}", format_row(&self.headers));
+        for row in self.rows.iter() {
+            println!("{}", format_row(row));
+        }
+    }
+}
@@ -2,7 +2,7 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
 
 use crate::{
-    utils::{get_treasury, treasury_tokens_pubkey},
+    utils::{format_timestamp, get_treasury, treasury_tokens_pubkey},
     Miner,
 };
 
@@ -16,8 +16,11 @@ impl Miner {
             let balance = treasury_tokens.token_amount.ui_amount_string;
             println!("{:} ORE", balance);
             println!("Admin: {}", treasury.admin);
-            println!("Difficulty: {}", treasury.difficulty.to_string());
-            println!("Last reset at: {}", treasury.last_reset_at);
+            println!("Difficulty: {}", treasury.difficulty);
+            println!(
+                "Last reset at: {}",
+                format_timestamp(treasury.last_reset_at)
+            );
             println!(
                 "Reward rate: {} ORE",
                 (treasury.reward_rate as f64) / 10f64.powf(ore::TOKEN_DECIMALS as f64)
@@ -1,15 +1,28 @@
+use std::{fs, path::PathBuf, time::Duration};
+
 use cached::proc_macro::cached;
+use chrono::{DateTime, Utc};
 use ore::{
     self,
-    state::{Proof, Treasury},
+    state::{Bus, Proof, Treasury},
     utils::AccountDeserialize,
-    MINT_ADDRESS, PROOF, TREASURY_ADDRESS,
+    BUS_ADDRESSES, MINT_ADDRESS, PROOF, TREASURY_ADDRESS,
+};
+use solana_client::{
+    client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, sysvar};
+use solana_sdk::{
+    account::Account, clock::Clock, commitment_config::CommitmentConfig, keccak::Hash as KeccakHash,
 };
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_program::{pubkey::Pubkey, sysvar};
-use solana_sdk::{clock::Clock, commitment_config::CommitmentConfig};
 use spl_associated_token_account::get_associated_token_address;
 
+use crate::cu_limits::CU_LIMIT_MINE;
+
+// The base fee charged per transaction signature, in lamports
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
 pub async fn get_treasury(cluster: String) -> Treasury {
     let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
     let data = client
@@ -47,3 +60,230 @@ pub fn proof_pubkey(authority: Pubkey) -> Pubkey {
 pub fn treasury_tokens_pubkey() -> Pubkey {
     get_associated_token_address(&TREASURY_ADDRESS, &MINT_ADDRESS)
 }
+
+pub fn amount_u64_to_f64(amount: u64) -> f64 {
+    (amount as f64) / 10f64.powf(ore::TOKEN_DECIMALS as f64)
+}
+
+pub fn lamports_to_sol(lamports: u64) -> f64 {
+    (lamports as f64) / (LAMPORTS_PER_SOL as f64)
+}
+
+pub fn format_timestamp(unix_timestamp: i64) -> String {
+    match DateTime::<Utc>::from_timestamp(unix_timestamp, 0) {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => unix_timestamp.to_string(),
+    }
+}
+
+/// Fetches accounts in chunks of `MAX_MULTIPLE_ACCOUNTS`, preserving the order of `addresses`.
+pub async fn get_multiple_accounts_batched(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+) -> ClientResult<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(client.get_multiple_accounts(chunk).await?);
+    }
+    Ok(accounts)
+}
+
+/// Returns the block time of the most recent transaction that touched the given proof.
+pub async fn get_last_proof_activity(client: &RpcClient, proof_address: Pubkey) -> Option<i64> {
+    let config = GetConfirmedSignaturesForAddress2Config {
+        limit: Some(1),
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    };
+    client
+        .get_signatures_for_address_with_config(&proof_address, config)
+        .await
+        .ok()?
+        .first()?
+        .block_time
+}
+
+/// A snapshot of the accounts a miner cares about while mining.
+pub struct MinerAccounts {
+    pub wallet_sol: u64,
+    pub wallet_ore: u64,
+    pub proof: Option<Proof>,
+    pub treasury: Option<Treasury>,
+    pub bus_rewards: Vec<u64>,
+}
+
+/// Fetches the wallet, token account, proof, treasury and busses of `authority` in one request.
+pub async fn get_miner_accounts(
+    client: &RpcClient,
+    authority: Pubkey,
+) -> ClientResult<MinerAccounts> {
+    let mut addresses = vec![
+        authority,
+        get_associated_token_address(&authority, &MINT_ADDRESS),
+        proof_pubkey(authority),
+        TREASURY_ADDRESS,
+    ];
+    addresses.extend(BUS_ADDRESSES.iter());
+    let accounts = client.get_multiple_accounts(&addresses).await?;
+    Ok(MinerAccounts {
+        wallet_sol: accounts[0].as_ref().map_or(0, |a| a.lamports),
+        wallet_ore: parse_token_amount(&accounts[1]),
+        proof: accounts[2]
+            .as_ref()
+            .and_then(|a| Proof::try_from_bytes(&a.data).ok().copied()),
+        treasury: accounts[3]
+            .as_ref()
+            .and_then(|a| Treasury::try_from_bytes(&a.data).ok().copied()),
+        bus_rewards: accounts[4..]
+            .iter()
+            .map(|a| {
+                a.as_ref()
+                    .and_then(|a| Bus::try_from_bytes(&a.data).ok())
+                    .map_or(0, |bus| bus.rewards)
+            })
+            .collect(),
+    })
+}
+
+/// Returns the number of hashes expected to find one at or below `difficulty`.
+pub fn expected_hashes(difficulty: KeccakHash) -> f64 {
+    let difficulty = difficulty
+        .to_bytes()
+        .iter()
+        .fold(0f64, |value, byte| value * 256.0 + *byte as f64);
+    2f64.powi(256) / (difficulty + 1.0)
+}
+
+pub fn parse_token_amount(account: &Option<Account>) -> u64 {
+    account
+        .as_ref()
+        .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+        .map(|token_account| token_account.amount)
+        .unwrap_or(0)
+}
+
+/// Estimates the fee of a single-signature transaction, in lamports.
+pub fn estimate_fee(cu_limit: u32, priority_fee: u64) -> u64 {
+    LAMPORTS_PER_SIGNATURE.saturating_add(
+        (cu_limit as u64)
+            .saturating_mul(priority_fee)
+            .div_ceil(1_000_000),
+    )
+}
+
+/// Returns the number of mine transactions `balance` lamports pays for at `priority_fee`.
+pub fn fee_runway(balance: u64, priority_fee: u64) -> u64 {
+    balance / estimate_fee(CU_LIMIT_MINE, priority_fee)
+}
+
+/// Parses durations like `90s`, `45m`, `2h` or `1h30m`. A bare number is read as seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(format!("Invalid duration unit '{}'", c)),
+        };
+        let amount: u64 = digits
+            .parse()
+            .map_err(|_| format!("Invalid duration '{}'", value))?;
+        total = total.saturating_add(amount.saturating_mul(unit));
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        total = total.saturating_add(
+            digits
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid duration '{}'", value))?,
+        );
+    }
+    if total == 0 {
+        return Err(format!("Invalid duration '{}'", value));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h {}m {}s", h, m, s)
+    } else if m > 0 {
+        format!("{}m {}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Formats a hashrate with a unit prefix, like `1.25 MH/s`.
+pub fn format_hashrate(hashrate: f64) -> String {
+    if hashrate >= 1e9 {
+        format!("{:.2} GH/s", hashrate / 1e9)
+    } else if hashrate >= 1e6 {
+        format!("{:.2} MH/s", hashrate / 1e6)
+    } else if hashrate >= 1e3 {
+        format!("{:.2} kH/s", hashrate / 1e3)
+    } else {
+        format!("{:.0} H/s", hashrate)
+    }
+}
+
+/// Parses hashrates like `850000`, `2.5M` or `1.2 GH/s`.
+pub fn parse_hashrate(value: &str) -> Result<f64, String> {
+    let value = value.trim();
+    let number = value.trim_end_matches(|c: char| c.is_alphabetic() || c == '/' || c == ' ');
+    let unit = value[number.len()..].trim().to_lowercase();
+    let scale = match unit.trim_end_matches("h/s").trim_end_matches('h') {
+        "" => 1e0,
+        "k" => 1e3,
+        "m" => 1e6,
+        "g" => 1e9,
+        _ => return Err(format!("Invalid hashrate unit: {}", unit)),
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0.0 => Ok(number * scale),
+        _ => Err(format!("Invalid hashrate: {}", value)),
+    }
+}
+
+/// Returns the directory the CLI keeps local state in, creating it if needed.
+pub fn state_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    let dir = PathBuf::from(home).join(".config").join("ore-cli");
+    fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Parses core lists like `0,2,4-7`.
+pub fn parse_core_list(value: &str) -> Result<Vec<usize>, String> {
+    let mut cores = vec![];
+    for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid core: {}", s))
+        };
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("Invalid core range: {}", part));
+                }
+                cores.extend(first..=last);
+            }
+            None => cores.push(parse(part)?),
+        }
+    }
+    if cores.is_empty() {
+        return Err("No cores given".to_string());
+    }
+    Ok(cores)
+}
@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use ore::{state::Proof, utils::AccountDeserialize, MINT_ADDRESS};
+use serde::Serialize;
+use solana_account_decoder::UiAccountData;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Signer},
+};
+
+use crate::{
+    cu_limits::CU_LIMIT_MINE,
+    table::Table,
+    utils::{
+        amount_u64_to_f64, estimate_fee, fee_runway, format_timestamp, get_last_proof_activity,
+        lamports_to_sol, parse_token_amount, proof_pubkey,
+    },
+    Miner, OutputFormat, WalletArgs,
+};
+
+#[derive(Serialize)]
+struct TokenAccountRow {
+    address: String,
+    ore: f64,
+}
+
+#[derive(Serialize)]
+struct WalletSummary {
+    address: String,
+    sol: f64,
+    token_account: String,
+    token_account_ore: Option<f64>,
+    other_token_accounts: Vec<TokenAccountRow>,
+    total_ore: f64,
+    proof: String,
+    registered: bool,
+    claimable_rewards: f64,
+    total_hashes: u64,
+    total_rewards: f64,
+    last_hash_at: Option<i64>,
+    priority_fee: u64,
+    mine_fee_sol: f64,
+    runway_transactions: u64,
+}
+
+impl Miner {
+    pub async fn wallet(&self, args: WalletArgs) {
+        // Accept an address or a keypair file
+        let address = match args.address {
+            Some(address) => match Pubkey::from_str(&address) {
+                Ok(address) => address,
+                Err(_) => match read_keypair_file(&address) {
+                    Ok(keypair) => keypair.pubkey(),
+                    Err(_) => {
+                        log::error!("Invalid address or keypair: {:?}", address);
+                        return;
+                    }
+                },
+            },
+            None => self.signer().pubkey(),
+        };
+        let client =
+            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+
+        // Fetch the wallet, token accounts and proof together
+        let token_account =
+            spl_associated_token_account::get_associated_token_address(&address, &MINT_ADDRESS);
+        let proof_address = proof_pubkey(address);
+        let addresses = [address, token_account, proof_address];
+        let (accounts, token_accounts, last_hash_at) = tokio::join!(
+            client.get_multiple_accounts(&addresses),
+            client.get_token_accounts_by_owner(&address, TokenAccountsFilter::Mint(MINT_ADDRESS)),
+            get_last_proof_activity(&client, proof_address),
+        );
+        let accounts = match accounts {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                log::error!("Error fetching accounts: {:?}", err);
+                return;
+            }
+        };
+        let other_token_accounts: Vec<TokenAccountRow> = match token_accounts {
+            Ok(token_accounts) => token_accounts
+                .into_iter()
+                .filter(|keyed| keyed.pubkey.ne(&token_account.to_string()))
+                .map(|keyed| TokenAccountRow {
+                    ore: match keyed.account.data {
+                        UiAccountData::Json(data) => data.parsed["info"]["tokenAmount"]["amount"]
+                            .as_str()
+                            .and_then(|amount| amount.parse().ok())
+                            .map_or(0.0, amount_u64_to_f64),
+                        _ => 0.0,
+                    },
+                    address: keyed.pubkey,
+                })
+                .collect(),
+            Err(err) => {
+                log::warn!("Error fetching other token accounts: {:?}", err);
+                vec![]
+            }
+        };
+
+        // Summarize
+        let sol = accounts[0].as_ref().map_or(0, |a| a.lamports);
+        let token_account_ore = accounts[1]
+            .as_ref()
+            .map(|_| amount_u64_to_f64(parse_token_amount(&accounts[1])));
+        let proof = accounts[2]
+            .as_ref()
+            .and_then(|a| Proof::try_from_bytes(&a.data).ok().copied());
+        let summary = WalletSummary {
+            address: address.to_string(),
+            sol: lamports_to_sol(sol),
+            token_account: token_account.to_string(),
+            token_account_ore,
+            total_ore: token_account_ore.unwrap_or(0.0)
+                + other_token_accounts.iter().map(|a| a.ore).sum::<f64>(),
+            other_token_accounts,
+            proof: proof_address.to_string(),
+            registered: proof.is_some(),
+            claimable_rewards: amount_u64_to_f64(proof.map_or(0, |p| p.claimable_rewards)),
+            total_hashes: proof.map_or(0, |p| p.total_hashes),
+            total_rewards: amount_u64_to_f64(proof.map_or(0, |p| p.total_rewards)),
+            last_hash_at,
+            priority_fee: self.priority_fee,
+            mine_fee_sol: lamports_to_sol(estimate_fee(CU_LIMIT_MINE, self.priority_fee)),
+            runway_transactions: fee_runway(sol, self.priority_fee),
+        };
+
+        // Print
+        match args.format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+            }
+            OutputFormat::Table | OutputFormat::Csv => {
+                let s = &summary;
+                let mut table = Table::new(&["Field", "Value"]);
+                let mut add = |field: &str, value: String| {
+                    table.add_row(vec![field.to_string(), value]);
+                };
+                add("Address", s.address.clone());
+                add("SOL", format!("{:.9}", s.sol));
+                add(
+                    "ORE account",
+                    match s.token_account_ore {
+                        Some(ore) => format!("{:.9} ({})", ore, s.token_account),
+                        None => format!("Not created ({})", s.token_account),
+                    },
+                );
+                for account in s.other_token_accounts.iter() {
+                    add(
+                        "Other ORE account",
+                        format!("{:.9} ({})", account.ore, account.address),
+                    );
+                }
+                add("Total ORE", format!("{:.9}", s.total_ore));
+                add(
+                    "Proof",
+                    match s.registered {
+                        true => s.proof.clone(),
+                        false => format!("Not registered ({})", s.proof),
+                    },
+                );
+                if s.registered {
+                    add("Claimable (ORE)", format!("{:.9}", s.claimable_rewards));
+                    add("Total hashes", s.total_hashes.to_string());
+                    add("Total rewards (ORE)", format!("{:.9}", s.total_rewards));
+                    add(
+                        "Last hash",
+                        s.last_hash_at.map_or("-".to_string(), format_timestamp),
+                    );
+                }
+                add(
+                    "Fee runway",
+                    format!(
+                        "~{} mine transactions at {:.9} SOL ({} microlamports priority fee)",
+                        s.runway_transactions, s.mine_fee_sol, s.priority_fee
+                    ),
+                );
+                if let OutputFormat::Csv = args.format {
+                    table.print_csv();
+                } else {
+                    table.print();
+                }
+            }
+        }
+    }
+}